@@ -51,6 +51,18 @@ function string_transport(handle, string) {
   }
 }
 
+// micropython.wasm only passes None, numbers and strings to JS, so bulk data
+// arrives base64 encoded
+function data_transport(base64) {
+  const raw_bytes = Uint8Array.from(atob(base64), c => c.charCodeAt(0))
+  const fat_pointer = Module.rust_instance.exports.data_transport(
+    raw_bytes.length)
+  const pointer = Number(fat_pointer >> BigInt(32))
+  
+  const memory = new Uint8Array(Module.rust_instance.exports.memory.buffer)
+  memory.set(raw_bytes, pointer)
+}
+
 function serialize() {
   const fat_pointer =  Module.rust_instance.exports.serialize()
   const offset = Number(fat_pointer >> BigInt(32))
@@ -72,6 +84,7 @@ export let proxy_js_ref = [{
   
   // Real functions
   string_transport,
+  data_transport,
   py_rust_call,
 }]
 
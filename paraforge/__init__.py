@@ -5,10 +5,12 @@ except ImportError:
     micropython = True
 
 if micropython:
-    import js
+    import js, binascii
 else:
     import os, ctypes
 
+import math, struct
+
 class ErrorCode:
     None_ = 0
     Mutex = 1
@@ -29,10 +31,33 @@ class ErrorCode:
     NotInitialized = 16
     SizeOutOfBounds = 17
     UnicodeError = 18
+    VtxOutOfBounds = 19
+    EmptyGeometry = 20
+    TriOutOfBounds = 21
+    AttributeMissing = 22
+    ParseError = 23
+    NotClosed = 24
+    EmptySelection = 25
+    TooManyVertices = 26
+    NotConvex = 27
+
+class SelectionType:
+    VERTICES = 0
+    TRIANGLES = 1
+
+class NormalWeighting:
+    Uniform = 0
+    Area = 1
+    Angle = 2
 
 class ParaforgeError(Exception):
-    def __init__(self, code: ErrorCode):
-        super().__init__(f'Code {code.value}: {code.name}')
+    def __init__(self, code: int):
+        # ErrorCode is a plain class (micropython has no enum module), so
+        # names are looked up among its attributes
+        names = [name for name in dir(ErrorCode)
+            if getattr(ErrorCode, name) == code]
+        super().__init__(f'Code {code}: {names[0]}')
+        self.code = code
 
 if not micropython:
     store = wasmtime.Store()
@@ -83,6 +108,15 @@ class Mesh:
 class PackedGeometry():
     @property
     def handle(self): return self._handle
+    
+    @property
+    def vtx_accessor(self) -> int:
+        return packed_get_vtx_accessor(self._handle)
+    
+    @property
+    def tri_accessor(self) -> int | None:
+        accessor = packed_get_tri_accessor(self._handle)
+        return None if accessor == 0xffffffff else accessor
 
 
 class Material:
@@ -154,9 +188,27 @@ class Geometry:
         result._handle = new_geometry_cube()
         return result
     
+    def New() -> 'Geometry':
+        result = Geometry()
+        result._handle = new_geometry()
+        return result
+    
+    def ImportOBJ(text: str) -> 'Geometry':
+        result = Geometry()
+        result._handle = import_obj(text)
+        return result
+    
     @property
     def handle(self): return self._handle
     
+    def free(self):
+        geometry_free(self._handle)
+    
+    def clone(self) -> 'Geometry':
+        result = Geometry()
+        result._handle = geometry_clone(self._handle)
+        return result
+    
     def t(self, x: int | float, y: int | float, z: int | float) -> 'Geometry':
         return self.translate(x, y, z)
     
@@ -173,6 +225,487 @@ class Geometry:
         geometry_scale(self._handle, float(x), float(y), float(z))
         return self
     
+    def rotate_euler_about(self,
+    pivot: tuple[int | float, int | float, int | float], x: int | float,
+    y: int | float, z: int | float) -> 'Geometry':
+        geometry_rotate_euler_about(self._handle, *map(float, pivot),
+            float(x), float(y), float(z))
+        return self
+    
+    def scale_about(self, pivot: tuple[int | float, int | float, int | float],
+    x: int | float, y: int | float, z: int | float) -> 'Geometry':
+        geometry_scale_about(self._handle, *map(float, pivot), float(x),
+            float(y), float(z))
+        return self
+    
+    def transform_matrix(self, m: list[int | float]) -> 'Geometry':
+        geometry_transform_matrix(self._handle, *map(float, m))
+        return self
+    
+    def array_radial(self, count: int,
+    axis: tuple[int | float, int | float, int | float] = (0, 0, 1),
+    ) -> 'Geometry':
+        geometry_array_radial(self._handle, count, *map(float, axis))
+        return self
+    
+    def clear(self) -> 'Geometry':
+        geometry_clear(self._handle)
+        return self
+    
+    def add_torus_knot(self, p: int, q: int, segments: int,
+    tube_radius: int | float) -> 'Geometry':
+        geometry_add_torus_knot(self._handle, p, q, segments,
+            float(tube_radius))
+        return self
+    
+    def add_frustum(self, segments: int, bottom_radius: int | float,
+    top_radius: int | float, unit: bool = False) -> 'Geometry':
+        geometry_add_frustum(self._handle, segments, float(bottom_radius),
+            float(top_radius), unit)
+        return self
+    
+    def add_text(self, text: str, size: int | float = 1,
+    depth: int | float = 0.2) -> 'Geometry':
+        geometry_add_text(self._handle, text, float(size), float(depth))
+        return self
+    
+    def add_grid(self, x_divisions: int, y_divisions: int, unit: bool = False,
+    ) -> 'Geometry':
+        geometry_add_grid(self._handle, x_divisions, y_divisions, unit)
+        return self
+    
+    def add_torus(self, major_segments: int = 24, minor_segments: int = 12,
+    minor_radius: int | float = 0.25) -> 'Geometry':
+        geometry_add_torus(self._handle, major_segments, minor_segments,
+            float(minor_radius))
+        return self
+    
+    def add_cone(self, segments: int, bottom_radius: int | float = 1,
+    top_radius: int | float = 0, unit: bool = False) -> 'Geometry':
+        geometry_add_cone(self._handle, segments, float(bottom_radius),
+            float(top_radius), unit)
+        return self
+    
+    def add_cylinder_open(self, segments: int, unit: bool = False,
+    ) -> 'Geometry':
+        geometry_add_cylinder_open(self._handle, segments, unit)
+        return self
+    
+    def add_sphere(self, rings: int = 8, segments: int = 16) -> 'Geometry':
+        geometry_add_sphere(self._handle, rings, segments)
+        return self
+    
+    def add_sphere_section(self, segments: int, rings: int,
+    start_angle: int | float, end_angle: int | float, cap: bool = False,
+    ) -> 'Geometry':
+        geometry_add_sphere_section(self._handle, segments, rings,
+            float(start_angle), float(end_angle), cap)
+        return self
+    
+    def add_stairs(self, steps: int, width: int | float, rise: int | float,
+    run: int | float) -> 'Geometry':
+        geometry_add_stairs(self._handle, steps, float(width), float(rise),
+            float(run))
+        return self
+    
+    def join(self, other: 'Geometry', keep_selection: bool = False,
+    ) -> 'Geometry':
+        if keep_selection:
+            geometry_join_keep(self._handle, other.handle)
+        else:
+            geometry_join(self._handle, other.handle)
+        return self
+    
+    def append(self, other: 'Geometry',
+    offset: tuple[int | float, int | float, int | float] = (0, 0, 0),
+    ) -> 'Geometry':
+        geometry_append(self._handle, other.handle, *map(float, offset))
+        return self
+    
+    def scatter(self, item: 'Geometry', align_to_normal: bool = False,
+    ) -> 'Geometry':
+        # Returns a new geometry, leaving this one unchanged
+        result = Geometry()
+        result._handle = geometry_scatter(self._handle, item.handle,
+            align_to_normal)
+        return result
+    
+    def union_convex(self, other: 'Geometry') -> 'Geometry':
+        # Both geometries must be closed and convex
+        geometry_union_convex(self._handle, other.handle)
+        return self
+    
+    def taper_profile(self, axis: int, samples: list[int | float],
+    axis_min: int | float, axis_max: int | float) -> 'Geometry':
+        write_data(struct.pack(f'<{len(samples)}d', *samples))
+        geometry_taper_profile(self._handle, axis, len(samples),
+            float(axis_min), float(axis_max))
+        return self
+    
+    def is_selected(self, vertex: int) -> bool:
+        return bool(geometry_is_selected(self._handle, vertex))
+    
+    def merge(self, x: int | float, y: int | float, z: int | float,
+    ) -> 'Geometry':
+        geometry_merge(self._handle, float(x), float(y), float(z))
+        return self
+    
+    def merge_center(self) -> 'Geometry':
+        geometry_merge_center(self._handle)
+        return self
+    
+    def stitch_boundaries(self, threshold: int | float) -> 'Geometry':
+        geometry_stitch_boundaries(self._handle, float(threshold))
+        return self
+    
+    def merge_average(self) -> 'Geometry':
+        geometry_merge_average(self._handle)
+        return self
+    
+    def collapse_short_edges(self, min_length: int | float) -> int:
+        return geometry_collapse_short_edges(self._handle, float(min_length))
+    
+    def weld(self, epsilon: int | float) -> int:
+        return geometry_weld(self._handle, float(epsilon))
+    
+    def remove_doubles_respect_normals(self, threshold: int | float,
+    max_angle: int | float) -> int:
+        return geometry_remove_doubles_respect_normals(self._handle,
+            float(threshold), float(max_angle))
+    
+    def repair(self) -> 'Geometry':
+        geometry_repair(self._handle)
+        return self
+    
+    def selected_vertex_count(self) -> int:
+        return geometry_get_selected_vtx_count(self._handle)
+    
+    def selected_triangle_count(self) -> int:
+        return geometry_get_selected_tri_count(self._handle)
+    
+    def nonmanifold_edge_count(self) -> int:
+        return geometry_get_nonmanifold_edge_count(self._handle)
+    
+    def hole_count(self) -> int:
+        return geometry_get_hole_count(self._handle)
+    
+    def is_winding_consistent(self) -> bool:
+        return bool(geometry_is_winding_consistent(self._handle))
+    
+    def closed_volume(self) -> float:
+        return geometry_get_closed_volume(self._handle)
+    
+    def volume_centroid(self) -> tuple[float, float, float]:
+        return tuple(geometry_get_volume_centroid(self._handle, axis)
+            for axis in range(3))
+    
+    def selection_centroid(self) -> tuple[float, float, float]:
+        return tuple(geometry_get_selection_centroid(self._handle, axis)
+            for axis in range(3))
+    
+    def aabb(self) -> tuple[tuple[float, float, float],
+    tuple[float, float, float]]:
+        values = struct.unpack('<6d', geometry_get_aabb(self._handle))
+        return (values[:3], values[3:])
+    
+    def selection_bounds(self) -> tuple[tuple[float, float, float],
+    tuple[float, float, float]]:
+        return (
+            tuple(geometry_get_selection_min(self._handle, axis)
+                for axis in range(3)),
+            tuple(geometry_get_selection_max(self._handle, axis)
+                for axis in range(3)),
+        )
+    
+    def min_triangle_quality(self) -> float:
+        return geometry_get_min_triangle_quality(self._handle)
+    
+    def count_tris_above_area(self, min_area: int | float) -> int:
+        return geometry_count_tris_above_area(self._handle, float(min_area))
+    
+    def surface_area(self) -> float:
+        return geometry_get_surface_area(self._handle)
+    
+    def selected_area(self) -> float:
+        return geometry_get_selected_area(self._handle)
+    
+    def quantize(self, step: int | float) -> 'Geometry':
+        geometry_quantize(self._handle, float(step))
+        return self
+    
+    def snap_to_plane(self, axis: int, value: int | float,
+    tolerance: int | float) -> 'Geometry':
+        geometry_snap_to_plane(self._handle, axis, float(value),
+            float(tolerance))
+        return self
+    
+    def normalize(self) -> 'Geometry':
+        geometry_normalize(self._handle)
+        return self
+    
+    def set_smooth_group(self, group: int) -> 'Geometry':
+        geometry_set_smooth_group(self._handle, group)
+        return self
+    
+    def set_crease(self, weight: int | float) -> 'Geometry':
+        geometry_set_crease(self._handle, float(weight))
+        return self
+    
+    def get_crease(self, a: int, b: int) -> float:
+        # 0 if the edge has no crease
+        return geometry_get_crease(self._handle, a, b)
+    
+    def compute_normals(self, weighting: int = NormalWeighting.Area,
+    ) -> 'Geometry':
+        geometry_compute_normals_weighted(self._handle, weighting)
+        return self
+    
+    def split_faces(self) -> 'Geometry':
+        geometry_split_faces(self._handle)
+        return self
+    
+    def compute_flat_normals(self) -> 'Geometry':
+        geometry_compute_flat_normals(self._handle)
+        return self
+    
+    def compute_normals_with_angle(self,
+    max_angle: int | float = math.radians(30)) -> 'Geometry':
+        geometry_compute_normals_with_angle(self._handle, float(max_angle))
+        return self
+    
+    def spin(self, steps: int, angle: int | float,
+    axis: tuple[int | float, int | float, int | float],
+    center: tuple[int | float, int | float, int | float] = (0, 0, 0),
+    ) -> 'Geometry':
+        geometry_spin(self._handle, steps, float(angle), *map(float, axis),
+            *map(float, center))
+        return self
+    
+    def subdivide(self, iterations: int = 1) -> 'Geometry':
+        geometry_subdivide(self._handle, iterations)
+        return self
+    
+    def smooth(self, iterations: int = 1, factor: int | float = 0.5,
+    ) -> 'Geometry':
+        geometry_smooth(self._handle, iterations, float(factor))
+        return self
+    
+    def displace_noise(self, amplitude: int | float, frequency: int | float,
+    seed: int = 0) -> 'Geometry':
+        geometry_displace_noise(self._handle, float(amplitude),
+            float(frequency), seed)
+        return self
+    
+    def solidify(self, thickness: int | float) -> 'Geometry':
+        geometry_solidify(self._handle, float(thickness))
+        return self
+    
+    def extrude_copy(self, x: int | float, y: int | float, z: int | float,
+    ) -> 'Geometry':
+        geometry_extrude_copy(self._handle, float(x), float(y), float(z))
+        return self
+    
+    def extrude_path(self,
+    path: list[tuple[int | float, int | float, int | float]],
+    ) -> 'Geometry':
+        # Profile is taken to sit at the first point
+        coordinates = [float(c) for point in path for c in point]
+        write_data(struct.pack(f'<{len(coordinates)}d', *coordinates))
+        geometry_extrude_path(self._handle, len(path))
+        return self
+    
+    def smooth_boundary(self, iterations: int = 1) -> 'Geometry':
+        geometry_smooth_boundary(self._handle, iterations)
+        return self
+    
+    def merge_coplanar(self, angle_tolerance: int | float) -> 'Geometry':
+        geometry_merge_coplanar(self._handle, float(angle_tolerance))
+        return self
+    
+    def thicken_profile(self, thickness: int | float) -> 'Geometry':
+        geometry_thicken_profile(self._handle, float(thickness))
+        return self
+    
+    def orient_like(self, reference: 'Geometry') -> 'Geometry':
+        geometry_orient_like(self._handle, reference._handle)
+        return self
+    
+    def closest_vertex(self, x: int | float, y: int | float, z: int | float,
+    ) -> int:
+        return geometry_closest_vtx(self._handle, float(x), float(y), float(z))
+    
+    def tri_neighbor(self, triangle: int, edge: int) -> int | None:
+        # None if the edge is open
+        neighbor = geometry_get_tri_neighbor(self._handle, triangle, edge)
+        return None if neighbor == 0xffffffff else neighbor
+    
+    def transfer_selection(self, target: 'Geometry', threshold: int | float,
+    ) -> 'Geometry':
+        geometry_transfer_selection(self._handle, target.handle,
+            float(threshold))
+        return self
+    
+    def set_normals_direction(self, x: int | float, y: int | float,
+    z: int | float) -> 'Geometry':
+        geometry_set_normals_direction(self._handle, float(x), float(y),
+            float(z))
+        return self
+    
+    def add_line(self, a: tuple[int | float, int | float, int | float],
+    b: tuple[int | float, int | float, int | float]) -> 'Geometry':
+        geometry_add_line(self._handle, *map(float, a), *map(float, b))
+        return self
+    
+    def principal_axes(self) -> list[tuple[float, float, float]]:
+        return [tuple(geometry_get_principal_axis(self._handle, which, i)
+            for i in range(3)) for which in range(3)]
+    
+    def lattice_deform(self, dx: int, dy: int, dz: int,
+    control_points: list[tuple[int | float, int | float, int | float]],
+    ) -> 'Geometry':
+        # Control points are ordered with x varying fastest and z slowest
+        coordinates = [float(c) for point in control_points for c in point]
+        write_data(struct.pack(f'<{len(coordinates)}d', *coordinates))
+        geometry_lattice_deform(self._handle, dx, dy, dz)
+        return self
+    
+    def select_similar_area(self, reference_triangle: int,
+    tolerance: int | float) -> 'Geometry':
+        geometry_select_similar_area(self._handle, reference_triangle,
+            float(tolerance))
+        return self
+    
+    def generate_uv_planar(self, axis: int) -> 'Geometry':
+        geometry_generate_uv_planar(self._handle, axis)
+        return self
+    
+    def generate_uv_box(self) -> 'Geometry':
+        geometry_generate_uv_box(self._handle)
+        return self
+    
+    def uv_project_camera(self,
+    position: tuple[int | float, int | float, int | float],
+    direction: tuple[int | float, int | float, int | float],
+    fov: int | float) -> 'Geometry':
+        geometry_uv_project_camera(self._handle, *map(float, position),
+            *map(float, direction), float(fov))
+        return self
+    
+    def loft(self, loops: list[list[int]]) -> 'Geometry':
+        data = [value for loop in loops for value in [len(loop), *loop]]
+        write_data(struct.pack(f'<{len(data)}I', *data))
+        geometry_loft(self._handle, len(loops))
+        return self
+    
+    def get_vertex(self, vertex: int) -> tuple[float, float, float]:
+        return struct.unpack('<3d', geometry_get_vtx(self._handle, vertex))
+    
+    def get_triangle(self, triangle: int) -> tuple[int, int, int]:
+        return struct.unpack('<3I', geometry_get_tri(self._handle, triangle))
+    
+    def get_normal(self, vertex: int) -> tuple[float, float, float]:
+        return tuple(geometry_get_normal(self._handle, vertex, axis)
+            for axis in range(3))
+    
+    def get_uv(self, vertex: int) -> tuple[float, float]:
+        return (geometry_get_uv(self._handle, vertex, 0),
+            geometry_get_uv(self._handle, vertex, 1))
+    
+    def set_uv(self, vertex: int, u: int | float, v: int | float,
+    ) -> 'Geometry':
+        geometry_set_uv(self._handle, vertex, float(u), float(v))
+        return self
+    
+    def set_vtx_color(self, vertex: int, r: int | float, g: int | float,
+    b: int | float, a: int | float = 1) -> 'Geometry':
+        geometry_set_vtx_color(self._handle, vertex, float(r), float(g),
+            float(b), float(a))
+        return self
+    
+    def transform_uv(self, a: int | float, b: int | float, c: int | float,
+    d: int | float, tx: int | float = 0, ty: int | float = 0) -> 'Geometry':
+        # Maps (u, v) to (a*u + b*v + tx, c*u + d*v + ty)
+        geometry_transform_uv(self._handle, float(a), float(b), float(c),
+            float(d), float(tx), float(ty))
+        return self
+    
+    def flip_uv(self, u: bool = True, v: bool = False) -> 'Geometry':
+        geometry_flip_uv(self._handle, int(u), int(v))
+        return self
+    
+    def scale_uv(self, su: int | float, sv: int | float,
+    pivot_u: int | float = 0, pivot_v: int | float = 0) -> 'Geometry':
+        geometry_scale_uv(self._handle, float(su), float(sv), float(pivot_u),
+            float(pivot_v))
+        return self
+    
+    def uv_pack(self, margin: int | float = 0) -> 'Geometry':
+        geometry_uv_pack(self._handle, float(margin))
+        return self
+    
+    def uv_unwrap(self, angle_threshold: int | float) -> 'Geometry':
+        geometry_uv_unwrap(self._handle, float(angle_threshold))
+        return self
+    
+    def retriangulate_shortest_diagonal(self) -> 'Geometry':
+        geometry_retriangulate_shortest(self._handle)
+        return self
+    
+    @property
+    def selection_mode(self) -> int:
+        return geometry_get_selection_mode(self._handle)
+    
+    @selection_mode.setter
+    def selection_mode(self, mode: int):
+        geometry_set_selection_mode(self._handle, mode)
+    
+    def set_pivot(self, x: int | float, y: int | float, z: int | float,
+    ) -> 'Geometry':
+        geometry_set_pivot(self._handle, float(x), float(y), float(z))
+        return self
+    
+    def raw_vertices(self) -> bytes:
+        return geometry_get_raw_vtx_ptr(self._handle)
+    
+    def raw_triangles(self) -> bytes:
+        return geometry_get_raw_tri_ptr(self._handle)
+    
+    def select_boundary(self) -> 'Geometry':
+        geometry_select_boundary(self._handle)
+        return self
+    
+    def select_grow(self) -> 'Geometry':
+        geometry_select_grow(self._handle)
+        return self
+    
+    def select_shrink(self) -> 'Geometry':
+        geometry_select_shrink(self._handle)
+        return self
+    
+    def select_by_normal(self,
+    direction: tuple[int | float, int | float, int | float],
+    max_angle: int | float) -> 'Geometry':
+        geometry_select_by_normal(self._handle, *map(float, direction),
+            float(max_angle))
+        return self
+    
+    def select_linked(self) -> 'Geometry':
+        geometry_select_linked(self._handle)
+        return self
+    
+    def select_invert(self) -> 'Geometry':
+        geometry_select_invert(self._handle)
+        return self
+    
+    def select_vertex_list(self, vertices: list[int]) -> 'Geometry':
+        write_data(struct.pack(f'<{len(vertices)}I', *vertices))
+        geometry_select_vertex_list(self._handle, len(vertices))
+        return self
+    
+    def select_creases(self, min_angle: int | float) -> 'Geometry':
+        geometry_select_creases(self._handle, float(min_angle))
+        return self
+    
     def select_triangles(self, x1: int | float, y1: int | float,
     z1: int | float, x2: int | float, y2: int | float, z2: int | float):
         geometry_select_triangles(self._handle, float(x1), float(y1), float(z1),
@@ -183,10 +716,21 @@ class Geometry:
         geometry_delete_triangles(self._handle)
         return self
     
+    def delete_stray_vertices(self):
+        geometry_delete_stray_vertices(self._handle)
+        return self
+    
     def pack(self) -> PackedGeometry:
         result = PackedGeometry()
         result._handle = geometry_pack(self._handle)
         return result
+    
+    def pack_shared(self, count: int) -> list[PackedGeometry]:
+        base = geometry_pack_shared(self._handle, count)
+        result = [PackedGeometry() for _ in range(count)]
+        for i, packed_geometry in enumerate(result):
+            packed_geometry._handle = base + i
+        return result
 
 
 def read_string(handle: int) -> str:
@@ -202,6 +746,16 @@ def write_string(handle: int, string: str):
         dst_ptr = wasm_call('string_transport', handle, size)
         ctypes.memmove(dst_ptr, raw_bytes, len(raw_bytes))
 
+def write_data(data: bytes):
+    if micropython:
+        js.data_transport(binascii.b2a_base64(data).decode())
+    else:
+        dst_ptr = wasm_call('data_transport', len(data))
+        ctypes.memmove(dst_ptr, data, len(data))
+
+def f32_from_bits(value: int) -> float:
+    return struct.unpack('<f', struct.pack('<I', value))[0]
+
 def wasm_call(function: str, *args):
     if micropython:
         # paraforge.wasm functions return i64...but micropython.wasm offers no
@@ -222,10 +776,9 @@ def wasm_call(function: str, *args):
         return value
     elif tag < 2**16:
         # Oh noes! A tag in this range must be an error code
-        try:
-            raise ParaforgeError(ErrorCode(value))
-        except ValueError as e:
-            raise ParaforgeError(ErrorCode.UnrecognizedErrorCode) from e
+        if value not in [getattr(ErrorCode, name) for name in dir(ErrorCode)]:
+            value = ErrorCode.UnrecognizedErrorCode
+        raise ParaforgeError(value)
     else:
         # Tags of 2^16 and higher are only used for returning fat pointers
         # to WebAssembly memory areas
@@ -235,6 +788,21 @@ def wasm_call(function: str, *args):
 def init():
     return wasm_call('init')
 
+def set_max_vertices(max: int):
+    return wasm_call('set_max_vertices', max)
+
+def set_buffer_per_mesh(enabled: bool):
+    return wasm_call('set_buffer_per_mesh', int(enabled))
+
+def checkpoint() -> int:
+    return wasm_call('checkpoint')
+
+def restore(id: int):
+    return wasm_call('restore', id)
+
+def checkpoint_free(id: int):
+    return wasm_call('checkpoint_free', id)
+
 def serialize() -> bytes:
     return bytes(wasm_call('serialize'))
 
@@ -258,12 +826,381 @@ def add_primitive_to_mesh(mesh: int, packed_geometry: int, material: int,
 def new_geometry_cube() -> int:
     return wasm_call('new_geometry_cube')
 
+def new_geometry() -> int:
+    return wasm_call('new_geometry')
+
+def geometry_free(handle: int):
+    return wasm_call('geometry_free', handle)
+
+def geometry_clone(handle: int) -> int:
+    return wasm_call('geometry_clone', handle)
+
+def import_obj(text: str) -> int:
+    data = text.encode()
+    write_data(data)
+    return wasm_call('import_obj', len(data))
+
+def geometry_clear(handle: int):
+    return wasm_call('geometry_clear', handle)
+
+def geometry_add_torus_knot(handle: int, p: int, q: int, segments: int,
+tube_radius: float):
+    return wasm_call('geometry_add_torus_knot', handle, p, q, segments,
+        tube_radius)
+
+def geometry_add_frustum(handle: int, segments: int, bottom_radius: float,
+top_radius: float, unit: bool):
+    return wasm_call('geometry_add_frustum', handle, segments, bottom_radius,
+        top_radius, int(unit))
+
+def geometry_add_text(handle: int, text: str, size: float, depth: float):
+    data = text.encode()
+    write_data(data)
+    return wasm_call('geometry_add_text', handle, len(data), size, depth)
+
+def geometry_add_grid(handle: int, x_divisions: int, y_divisions: int,
+unit: bool):
+    return wasm_call('geometry_add_grid', handle, x_divisions, y_divisions,
+        int(unit))
+
+def geometry_add_torus(handle: int, major_segments: int, minor_segments: int,
+minor_radius: float):
+    return wasm_call('geometry_add_torus', handle, major_segments,
+        minor_segments, minor_radius)
+
+def geometry_add_cone(handle: int, segments: int, bottom_radius: float,
+top_radius: float, unit: bool):
+    return wasm_call('geometry_add_cone', handle, segments, bottom_radius,
+        top_radius, int(unit))
+
+def geometry_add_cylinder_open(handle: int, segments: int, unit: bool):
+    return wasm_call('geometry_add_cylinder_open', handle, segments, int(unit))
+
+def geometry_add_sphere(handle: int, rings: int, segments: int):
+    return wasm_call('geometry_add_sphere', handle, rings, segments)
+
+def geometry_add_sphere_section(handle: int, segments: int, rings: int,
+start_angle: float, end_angle: float, cap: bool):
+    return wasm_call('geometry_add_sphere_section', handle, segments, rings,
+        start_angle, end_angle, int(cap))
+
+def geometry_add_stairs(handle: int, steps: int, width: float, rise: float,
+run: float):
+    return wasm_call('geometry_add_stairs', handle, steps, width, rise, run)
+
+def geometry_join(dst: int, src: int):
+    return wasm_call('geometry_join', dst, src)
+
+def geometry_append(dst: int, src: int, x: float, y: float, z: float):
+    return wasm_call('geometry_append', dst, src, x, y, z)
+
+def geometry_scatter(base: int, item: int, align_to_normal: bool) -> int:
+    return wasm_call('geometry_scatter', base, item, int(align_to_normal))
+
+def geometry_union_convex(dst: int, src: int):
+    return wasm_call('geometry_union_convex', dst, src)
+
+def geometry_join_keep(dst: int, src: int):
+    return wasm_call('geometry_join_keep', dst, src)
+
 def geometry_translate(handle: int, x: float, y: float, z: float):
     return wasm_call('geometry_translate', handle, x, y, z)
 
 def geometry_scale(handle: int, x: float, y: float, z: float):
     return wasm_call('geometry_scale', handle, x, y, z)
 
+def geometry_rotate_euler_about(handle: int, px: float, py: float, pz: float,
+x: float, y: float, z: float):
+    return wasm_call('geometry_rotate_euler_about', handle, px, py, pz, x, y, z)
+
+def geometry_array_radial(handle: int, count: int, ax: float, ay: float,
+az: float):
+    return wasm_call('geometry_array_radial', handle, count, ax, ay, az)
+
+def geometry_scale_about(handle: int, px: float, py: float, pz: float,
+x: float, y: float, z: float):
+    return wasm_call('geometry_scale_about', handle, px, py, pz, x, y, z)
+
+def geometry_transform_matrix(handle: int, *m: float):
+    return wasm_call('geometry_transform_matrix', handle, *m)
+
+def geometry_taper_profile(handle: int, axis: int, sample_count: int,
+axis_min: float, axis_max: float):
+    return wasm_call('geometry_taper_profile', handle, axis, sample_count,
+        axis_min, axis_max)
+
+def geometry_is_selected(handle: int, vtx: int) -> int:
+    return wasm_call('geometry_is_selected', handle, vtx)
+
+def geometry_merge(handle: int, x: float, y: float, z: float):
+    return wasm_call('geometry_merge', handle, x, y, z)
+
+def geometry_merge_center(handle: int):
+    return wasm_call('geometry_merge_center', handle)
+
+def geometry_stitch_boundaries(handle: int, threshold: float):
+    return wasm_call('geometry_stitch_boundaries', handle, threshold)
+
+def geometry_merge_average(handle: int):
+    return wasm_call('geometry_merge_average', handle)
+
+def geometry_collapse_short_edges(handle: int, min_length: float) -> int:
+    return wasm_call('geometry_collapse_short_edges', handle, min_length)
+
+def geometry_weld(handle: int, epsilon: float) -> int:
+    return wasm_call('geometry_weld', handle, epsilon)
+
+def geometry_remove_doubles_respect_normals(handle: int, threshold: float,
+max_angle: float) -> int:
+    return wasm_call('geometry_remove_doubles_respect_normals', handle,
+        threshold, max_angle)
+
+def geometry_repair(handle: int):
+    return wasm_call('geometry_repair', handle)
+
+def geometry_get_selected_vtx_count(handle: int) -> int:
+    return wasm_call('geometry_get_selected_vtx_count', handle)
+
+def geometry_get_selected_tri_count(handle: int) -> int:
+    return wasm_call('geometry_get_selected_tri_count', handle)
+
+def geometry_get_nonmanifold_edge_count(handle: int) -> int:
+    return wasm_call('geometry_get_nonmanifold_edge_count', handle)
+
+def geometry_get_hole_count(handle: int) -> int:
+    return wasm_call('geometry_get_hole_count', handle)
+
+def geometry_is_winding_consistent(handle: int) -> int:
+    return wasm_call('geometry_is_winding_consistent', handle)
+
+def geometry_get_closed_volume(handle: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_closed_volume', handle))
+
+def geometry_get_volume_centroid(handle: int, axis: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_volume_centroid', handle,
+        axis))
+
+def geometry_get_selection_min(handle: int, axis: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_selection_min', handle, axis))
+
+def geometry_get_selection_max(handle: int, axis: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_selection_max', handle, axis))
+
+def geometry_get_selection_centroid(handle: int, axis: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_selection_centroid', handle,
+        axis))
+
+def geometry_get_min_triangle_quality(handle: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_min_triangle_quality',
+        handle))
+
+def geometry_count_tris_above_area(handle: int, min_area: float) -> int:
+    return wasm_call('geometry_count_tris_above_area', handle, min_area)
+
+def geometry_get_surface_area(handle: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_surface_area', handle))
+
+def geometry_get_selected_area(handle: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_selected_area', handle))
+
+def geometry_quantize(handle: int, step: float):
+    return wasm_call('geometry_quantize', handle, step)
+
+def geometry_snap_to_plane(handle: int, axis: int, value: float,
+tolerance: float):
+    return wasm_call('geometry_snap_to_plane', handle, axis, value, tolerance)
+
+def geometry_normalize(handle: int):
+    return wasm_call('geometry_normalize', handle)
+
+def geometry_compute_normals(handle: int):
+    return wasm_call('geometry_compute_normals', handle)
+
+def geometry_set_smooth_group(handle: int, group: int):
+    return wasm_call('geometry_set_smooth_group', handle, group)
+
+def geometry_set_crease(handle: int, weight: float):
+    return wasm_call('geometry_set_crease', handle, weight)
+
+def geometry_get_crease(handle: int, a: int, b: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_crease', handle, a, b))
+
+def geometry_compute_normals_weighted(handle: int, weighting: int):
+    return wasm_call('geometry_compute_normals_weighted', handle, weighting)
+
+def geometry_split_faces(handle: int):
+    return wasm_call('geometry_split_faces', handle)
+
+def geometry_compute_flat_normals(handle: int):
+    return wasm_call('geometry_compute_flat_normals', handle)
+
+def geometry_compute_normals_with_angle(handle: int, max_angle: float):
+    return wasm_call('geometry_compute_normals_with_angle', handle, max_angle)
+
+def geometry_spin(handle: int, steps: int, angle: float, ax: float,
+ay: float, az: float, cx: float, cy: float, cz: float):
+    return wasm_call('geometry_spin', handle, steps, angle, ax, ay, az, cx, cy,
+        cz)
+
+def geometry_subdivide(handle: int, iterations: int):
+    return wasm_call('geometry_subdivide', handle, iterations)
+
+def geometry_smooth(handle: int, iterations: int, factor: float):
+    return wasm_call('geometry_smooth', handle, iterations, factor)
+
+def geometry_displace_noise(handle: int, amplitude: float, frequency: float,
+seed: int):
+    return wasm_call('geometry_displace_noise', handle, amplitude, frequency,
+        seed)
+
+def geometry_solidify(handle: int, thickness: float):
+    return wasm_call('geometry_solidify', handle, thickness)
+
+def geometry_extrude_copy(handle: int, x: float, y: float, z: float):
+    return wasm_call('geometry_extrude_copy', handle, x, y, z)
+
+def geometry_extrude_path(handle: int, point_count: int):
+    return wasm_call('geometry_extrude_path', handle, point_count)
+
+def geometry_smooth_boundary(handle: int, iterations: int):
+    return wasm_call('geometry_smooth_boundary', handle, iterations)
+
+def geometry_merge_coplanar(handle: int, angle_tolerance: float):
+    return wasm_call('geometry_merge_coplanar', handle, angle_tolerance)
+
+def geometry_thicken_profile(handle: int, thickness: float):
+    return wasm_call('geometry_thicken_profile', handle, thickness)
+
+def geometry_orient_like(handle: int, reference_handle: int):
+    return wasm_call('geometry_orient_like', handle, reference_handle)
+
+def geometry_closest_vtx(handle: int, x: float, y: float, z: float) -> int:
+    return wasm_call('geometry_closest_vtx', handle, x, y, z)
+
+def geometry_get_tri_neighbor(handle: int, tri: int, edge: int) -> int:
+    return wasm_call('geometry_get_tri_neighbor', handle, tri, edge)
+
+def geometry_transfer_selection(src: int, dst: int, threshold: float):
+    return wasm_call('geometry_transfer_selection', src, dst, threshold)
+
+def geometry_set_normals_direction(handle: int, x: float, y: float,
+z: float):
+    return wasm_call('geometry_set_normals_direction', handle, x, y, z)
+
+def geometry_add_line(handle: int, ax: float, ay: float, az: float,
+bx: float, by: float, bz: float):
+    return wasm_call('geometry_add_line', handle, ax, ay, az, bx, by, bz)
+
+def geometry_get_principal_axis(handle: int, which: int, component: int,
+) -> float:
+    return f32_from_bits(wasm_call('geometry_get_principal_axis', handle,
+        which, component))
+
+def geometry_lattice_deform(handle: int, dx: int, dy: int, dz: int):
+    return wasm_call('geometry_lattice_deform', handle, dx, dy, dz)
+
+def geometry_select_similar_area(handle: int, reference_tri: int,
+tolerance: float):
+    return wasm_call('geometry_select_similar_area', handle, reference_tri,
+        tolerance)
+
+def geometry_uv_project_camera(handle: int, px: float, py: float, pz: float,
+dx: float, dy: float, dz: float, fov: float):
+    return wasm_call('geometry_uv_project_camera', handle, px, py, pz, dx, dy,
+        dz, fov)
+
+def geometry_generate_uv_planar(handle: int, axis: int):
+    return wasm_call('geometry_generate_uv_planar', handle, axis)
+
+def geometry_generate_uv_box(handle: int):
+    return wasm_call('geometry_generate_uv_box', handle)
+
+def geometry_loft(handle: int, loop_count: int):
+    return wasm_call('geometry_loft', handle, loop_count)
+
+def geometry_get_normal(handle: int, vtx: int, axis: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_normal', handle, vtx, axis))
+
+def geometry_get_uv(handle: int, vtx: int, component: int) -> float:
+    return f32_from_bits(wasm_call('geometry_get_uv', handle, vtx, component))
+
+def geometry_set_uv(handle: int, vtx: int, u: float, v: float):
+    return wasm_call('geometry_set_uv', handle, vtx, u, v)
+
+def geometry_set_vtx_color(handle: int, vtx: int, r: float, g: float,
+b: float, a: float):
+    return wasm_call('geometry_set_vtx_color', handle, vtx, r, g, b, a)
+
+def geometry_transform_uv(handle: int, a: float, b: float, c: float,
+d: float, tx: float, ty: float):
+    return wasm_call('geometry_transform_uv', handle, a, b, c, d, tx, ty)
+
+def geometry_flip_uv(handle: int, u: int, v: int):
+    return wasm_call('geometry_flip_uv', handle, u, v)
+
+def geometry_scale_uv(handle: int, su: float, sv: float, pu: float,
+pv: float):
+    return wasm_call('geometry_scale_uv', handle, su, sv, pu, pv)
+
+def geometry_uv_pack(handle: int, margin: float):
+    return wasm_call('geometry_uv_pack', handle, margin)
+
+def geometry_uv_unwrap(handle: int, angle_threshold: float):
+    return wasm_call('geometry_uv_unwrap', handle, angle_threshold)
+
+def geometry_retriangulate_shortest(handle: int):
+    return wasm_call('geometry_retriangulate_shortest', handle)
+
+def geometry_set_selection_mode(handle: int, mode: int):
+    return wasm_call('geometry_set_selection_mode', handle, mode)
+
+def geometry_get_selection_mode(handle: int) -> int:
+    return wasm_call('geometry_get_selection_mode', handle)
+
+def geometry_set_pivot(handle: int, x: float, y: float, z: float):
+    return wasm_call('geometry_set_pivot', handle, x, y, z)
+
+def geometry_get_raw_vtx_ptr(handle: int) -> bytes:
+    return bytes(wasm_call('geometry_get_raw_vtx_ptr', handle))
+
+def geometry_get_raw_tri_ptr(handle: int) -> bytes:
+    return bytes(wasm_call('geometry_get_raw_tri_ptr', handle))
+
+def geometry_get_vtx(handle: int, vtx: int) -> bytes:
+    return bytes(wasm_call('geometry_get_vtx', handle, vtx))
+
+def geometry_get_tri(handle: int, tri: int) -> bytes:
+    return bytes(wasm_call('geometry_get_tri', handle, tri))
+
+def geometry_get_aabb(handle: int) -> bytes:
+    return bytes(wasm_call('geometry_get_aabb', handle))
+
+def geometry_select_boundary(handle: int):
+    return wasm_call('geometry_select_boundary', handle)
+
+def geometry_select_grow(handle: int):
+    return wasm_call('geometry_select_grow', handle)
+
+def geometry_select_shrink(handle: int):
+    return wasm_call('geometry_select_shrink', handle)
+
+def geometry_select_by_normal(handle: int, dx: float, dy: float, dz: float,
+max_angle: float):
+    return wasm_call('geometry_select_by_normal', handle, dx, dy, dz,
+        max_angle)
+
+def geometry_select_linked(handle: int):
+    return wasm_call('geometry_select_linked', handle)
+
+def geometry_select_invert(handle: int):
+    return wasm_call('geometry_select_invert', handle)
+
+def geometry_select_vertex_list(handle: int, count: int):
+    return wasm_call('geometry_select_vertex_list', handle, count)
+
+def geometry_select_creases(handle: int, min_angle: float):
+    return wasm_call('geometry_select_creases', handle, min_angle)
+
 def geometry_select_triangles(handle: int, x1: float, y1: float, z1: float,
 x2: float, y2: float, z2: float):
     return wasm_call('geometry_select_triangles', handle, x1, y1, z1, x2, y2,
@@ -272,5 +1209,17 @@ x2: float, y2: float, z2: float):
 def geometry_delete_triangles(handle: int):
     return wasm_call('geometry_delete_triangles', handle)
 
+def geometry_delete_stray_vertices(handle: int):
+    return wasm_call('geometry_delete_stray_vertices', handle)
+
 def geometry_pack(handle: int) -> int:
     return wasm_call('geometry_pack', handle)
+
+def geometry_pack_shared(handle: int, count: int) -> int:
+    return wasm_call('geometry_pack_shared', handle, count)
+
+def packed_get_vtx_accessor(packed: int) -> int:
+    return wasm_call('packed_get_vtx_accessor', packed)
+
+def packed_get_tri_accessor(packed: int) -> int:
+    return wasm_call('packed_get_tri_accessor', packed)
@@ -2,7 +2,7 @@ fn argument_type_error(node: impl syn::spanned::Spanned,
 ) -> proc_macro::TokenStream {
   quote::quote_spanned! {
     node.span() => compile_error!("FFI arguments must be `u32`, `u64`, `i32`, \
-      `i64`, `f32`, or `f64`");
+      `i64`, `f32`, `f64`, or `bool`");
   }.into()
 }
 
@@ -14,13 +14,13 @@ pub fn ffi(
   let mut input_fn = syn::parse_macro_input!(input as syn::ItemFn);
   let signature = input_fn.sig.clone();
   let base_name = signature.ident.clone();
-  let args = signature.inputs.clone();
+  let mut args = signature.inputs.clone();
   
   let private_name = syn::Ident::new(format!("__{base_name}").as_str(),
     base_name.clone().span());
   
-  let mut arg_names: syn::punctuated::Punctuated<syn::Pat, syn::token::Comma> =
-    syn::punctuated::Punctuated::new();
+  let mut arg_values: syn::punctuated::Punctuated<syn::Expr,
+    syn::token::Comma> = syn::punctuated::Punctuated::new();
   
   input_fn.sig.ident = private_name.clone();
   
@@ -33,16 +33,27 @@ pub fn ffi(
     syn::parse_str("i64").unwrap(),
     syn::parse_str("f32").unwrap(),
     syn::parse_str("f64").unwrap(),
+    syn::parse_str("bool").unwrap(), // Lowered to u32 at the ABI boundary
   ];
+  let bool_type: syn::Type = syn::parse_str("bool").unwrap();
   
-  for pair in args.clone().into_pairs() {
-    match pair.into_tuple().0 {
+  for arg in args.iter_mut() {
+    match arg {
       syn::FnArg::Receiver(receiver) => return argument_type_error(receiver),
       syn::FnArg::Typed(pat_type) => {
         if !expected_argument_types.contains(&pat_type.ty) {
-          return argument_type_error(pat_type.ty);
+          return argument_type_error(pat_type.ty.clone());
+        }
+        
+        let pat = &pat_type.pat;
+        if *pat_type.ty == bool_type {
+          // WebAssembly has no bool type, so the exported function takes a
+          // u32 and any nonzero value is true
+          arg_values.push(syn::parse_quote! { #pat != 0 });
+          *pat_type.ty = syn::parse_str("u32").unwrap();
+        } else {
+          arg_values.push(syn::parse_quote! { #pat });
         }
-        arg_names.push(*pat_type.pat);
       },
     }
   }
@@ -55,7 +66,7 @@ pub fn ffi(
     pub extern "C" fn #base_name(#args) -> u64 {
       // Variable declaration is mainly to declare type and trigger type
       // enforcement
-      let result: FFIResult<_> = #private_name(#arg_names);
+      let result: FFIResult<_> = #private_name(#arg_values);
       
       match result {
         Err(code) => return 0x100000000 + code as u64,
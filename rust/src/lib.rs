@@ -1,6 +1,8 @@
+use std::collections::HashMap;
 use std::sync::{Mutex, MutexGuard};
 
 pub use nalgebra::Vector3 as V3;
+use nalgebra::{Matrix3, Matrix4, Point3, Rotation3, Unit};
 
 use paraforge_macros::ffi;
 
@@ -11,12 +13,19 @@ use paraforge_macros::ffi;
 // Statics are use to hold Paraforge's working data. They allow storing
 // persistent data structures that can be modified across different FFI calls
 
-static GEOMETRIES: Mutex<Vec<Geometry>> = Mutex::new(Vec::new());
+static GEOMETRIES: Mutex<GeometrySlots> = Mutex::new(GeometrySlots(
+  Vec::new()));
 static PACKED_GEOMETRIES: Mutex<Vec<PackedGeometry>> = Mutex::new(Vec::new());
 static STRING_TRANSPORT: Mutex<[Vec<u8>; 4]> = Mutex::new([vec![], vec![],
   vec![], vec![]]);
 static GLTF_SOURCE: Mutex<Option<GLTF>> = Mutex::new(None);
 static GLTF_OUTPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+static DATA_TRANSPORT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+static RAW_VTX_OUTPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+static RAW_TRI_OUTPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+static QUERY_OUTPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+static MAX_VERTICES: Mutex<u32> = Mutex::new(4_000_000);
+static CHECKPOINTS: Mutex<Vec<Option<Checkpoint>>> = Mutex::new(Vec::new());
 
 fn lock<'a, T>(mutex: &'a Mutex<T>) -> Result<MutexGuard<'a, T>, ErrorCode> {
   match mutex.lock() {
@@ -25,6 +34,45 @@ fn lock<'a, T>(mutex: &'a Mutex<T>) -> Result<MutexGuard<'a, T>, ErrorCode> {
   }
 }
 
+/// Geometry handles are indices into this. Freeing a geometry leaves its slot
+/// empty, so other handles stay valid and the freed handle gives an error
+#[derive(Clone)]
+struct GeometrySlots(Vec<Option<Geometry>>);
+
+impl GeometrySlots {
+  /// Whether `handle` refers to a geometry that has not been freed
+  fn contains(&self, handle: usize) -> bool {
+    return self.0.get(handle).is_some_and(Option::is_some);
+  }
+  
+  fn len(&self) -> usize {
+    return self.0.len();
+  }
+  
+  fn push(&mut self, geometry: Geometry) {
+    self.0.push(Some(geometry));
+  }
+  
+  fn free(&mut self, handle: usize) {
+    self.0[handle] = None;
+  }
+}
+
+// Callers check .contains() first, and return HandleOutOfBounds if it fails
+impl std::ops::Index<usize> for GeometrySlots {
+  type Output = Geometry;
+  
+  fn index(&self, handle: usize) -> &Geometry {
+    return self.0[handle].as_ref().expect("Geometry handle was freed");
+  }
+}
+
+impl std::ops::IndexMut<usize> for GeometrySlots {
+  fn index_mut(&mut self, handle: usize) -> &mut Geometry {
+    return self.0[handle].as_mut().expect("Geometry handle was freed");
+  }
+}
+
 fn get_string_transport(handle: usize) -> FFIResult<String> {
   let string_transport = lock(&STRING_TRANSPORT)?;
   
@@ -50,6 +98,38 @@ fn string_transport(handle: usize, size: usize) -> FFIResult<FatPointer> {
   return FatPointer::try_from(&string_transport[handle]);
 }
 
+/// Reads the first `count` little-endian f64s out of the data transport
+fn get_data_transport_f64(count: usize) -> FFIResult<Vec<f64>> {
+  let data_transport = lock(&DATA_TRANSPORT)?;
+  
+  if data_transport.len()/8 < count { return Err(ErrorCode::SizeOutOfBounds) };
+  
+  let (chunks, _) = data_transport[..count*8].as_chunks::<8>();
+  return Ok(chunks.iter().map(|chunk| f64::from_le_bytes(*chunk)).collect());
+}
+
+/// Reads every complete little-endian u32 out of the data transport
+fn get_data_transport_u32() -> FFIResult<Vec<u32>> {
+  let data_transport = lock(&DATA_TRANSPORT)?;
+  
+  let (chunks, _) = data_transport.as_chunks::<4>();
+  return Ok(chunks.iter().map(|chunk| u32::from_le_bytes(*chunk)).collect());
+}
+
+/// Staging area for bulk arguments (lookup tables, point lists, etc.) that do
+/// not fit in the string transports. Works like string_transport(), except
+/// there is only one buffer and no size limit
+#[ffi]
+fn data_transport(size: usize) -> FFIResult<FatPointer> {
+  let mut data_transport = lock(&DATA_TRANSPORT)?;
+  
+  if size != 0xffffffff {
+    data_transport.resize(size, 0);
+  }
+  
+  return FatPointer::try_from(data_transport.as_ref());
+}
+
 ////////////////////
 // Error Handling //
 ////////////////////
@@ -61,6 +141,21 @@ fn string_transport(handle: usize, size: usize) -> FFIResult<FatPointer> {
 pub trait FFIValue           { fn pack(self) -> u64; }
 impl FFIValue for ()         { fn pack(self) -> u64 { 0           } }
 impl FFIValue for usize      { fn pack(self) -> u64 { self as u64 } }
+// Floats are returned as their bit patterns, so callers must bit-cast them
+// back. Float queries return f32, which fits under the tag like any other
+// value and is decoded with f32_from_bits() in the Python library
+impl FFIValue for f32        { fn pack(self) -> u64 {
+  self.to_bits() as u64
+} }
+// An f64 fills all 64 bits, and positive subnormals have the same bit patterns
+// as errors (0x100000000 + code). Subnormals are flushed to zero so that any
+// result with a high word from 1 to 0xffff is unambiguously an error. A
+// wrapper can't read its tag as a fat pointer, so it must check for that range
+// and otherwise bit-cast all 64 bits
+impl FFIValue for f64        { fn pack(self) -> u64 {
+  if self.is_subnormal() { return 0.0f64.copysign(self).to_bits() };
+  self.to_bits()
+} }
 impl FFIValue for FatPointer { fn pack(self) -> u64 {
   ((self.offset as u64) << 32) + self.size as u64
 } }
@@ -115,6 +210,22 @@ pub enum ErrorCode {
   NotInitialized = 16,
   SizeOutOfBounds = 17,
   UnicodeError = 18,
+  VtxOutOfBounds = 19,
+  EmptyGeometry = 20,
+  TriOutOfBounds = 21,
+  // A per-vertex attribute (normals, UVs, etc.) was needed but has not been
+  // generated, or was cleared by a later operation
+  AttributeMissing = 22,
+  // Imported text (such as OBJ) was malformed
+  ParseError = 23,
+  // Operation needs a watertight mesh
+  NotClosed = 24,
+  // Operation needs at least one selected vertex or triangle
+  EmptySelection = 25,
+  // A primitive would take a geometry past the limit set by set_max_vertices()
+  TooManyVertices = 26,
+  // Operation needs a convex mesh
+  NotConvex = 27,
 }
 
 // Any value type T used inside an FFIResult should implement FFIValue, but
@@ -133,25 +244,165 @@ type FFIResult<T> = Result<T, ErrorCode>;
 // Non-GLTF Data Structures //
 //////////////////////////////
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum SelectionType {
-  VERTICES,
-  TRIANGLES,
+  VERTICES = 0,
+  TRIANGLES = 1,
+}
+
+impl TryFrom<u32> for SelectionType {
+  type Error = ErrorCode;
+  
+  fn try_from(value: u32) -> Result<Self, ErrorCode> {
+    match value {
+      0 => return Ok(Self::VERTICES),
+      1 => return Ok(Self::TRIANGLES),
+      _ => return Err(ErrorCode::ParameterOutOfRange),
+    }
+  }
+}
+
+/// How face normals are weighted when averaged into vertex normals
+#[derive(Copy, Clone, PartialEq)]
+pub enum NormalWeighting {
+  Uniform = 0,
+  Area = 1,
+  Angle = 2,
+}
+
+impl TryFrom<u32> for NormalWeighting {
+  type Error = ErrorCode;
+  
+  fn try_from(value: u32) -> Result<Self, ErrorCode> {
+    match value {
+      0 => return Ok(Self::Uniform),
+      1 => return Ok(Self::Area),
+      2 => return Ok(Self::Angle),
+      _ => return Err(ErrorCode::ParameterOutOfRange),
+    }
+  }
+}
+
+/// Number of sides used for the cross section of swept tubes
+pub const TUBE_SIDES: u32 = 8;
+
+/// Buckets points into cubic cells so points near a location can be found
+/// without scanning every point
+pub struct SpatialGrid {
+  cell_size: f64,
+  cells: HashMap<(i64, i64, i64), Vec<u32>>,
+}
+
+impl SpatialGrid {
+  pub fn new(points: &[V3<f64>], cell_size: f64) -> Self {
+    let mut result = Self { cell_size, cells: HashMap::new() };
+    
+    for (i, point) in points.iter().enumerate() {
+      result.cells.entry(result.cell(point)).or_default().push(i as u32);
+    }
+    
+    result
+  }
+  
+  /// Coordinates too large for an i64 saturate, so far away points share the
+  /// edge cells instead of overflowing
+  fn cell(&self, point: &V3<f64>) -> (i64, i64, i64) {
+    let scaled = point/self.cell_size;
+    (scaled.x.floor() as i64, scaled.y.floor() as i64, scaled.z.floor() as i64)
+  }
+  
+  /// Indices of points in the cell containing `point` and the 26 cells around
+  /// it. This includes every point within `cell_size` of `point`
+  pub fn nearby(&self, point: &V3<f64>) -> impl Iterator<Item = u32> + '_ {
+    let (x, y, z) = self.cell(point);
+    
+    (-1..=1).flat_map(move |dx| (-1..=1).flat_map(move |dy| {
+      (-1..=1).map(move |dz| {
+        (x.saturating_add(dx), y.saturating_add(dy), z.saturating_add(dz))
+      })
+    })).flat_map(|key| self.cells.get(&key).into_iter().flatten().copied())
+  }
 }
 
+#[derive(Clone)]
 pub struct Geometry {
   pub vertices: Vec<V3<f64>>,
   
   pub triangles: Vec<[u32; 3]>,
   
+  /// Line segments, packed as a separate primitive with lines mode. Useful
+  /// for debug overlays
+  pub edges: Vec<[u32; 2]>,
+  
   pub selection: Vec<u32>,
   pub selection_type: SelectionType,
+  
+  /// Per-vertex normals. None until computed, and cleared by operations that
+  /// add or remove vertices
+  pub normals: Option<Vec<V3<f64>>>,
+  
+  /// Per-vertex texture coordinates, packed as TEXCOORD_0. Same lifecycle as
+  /// .normals
+  pub uvs: Option<Vec<[f64; 2]>>,
+  
+  /// Per-vertex RGBA colors, packed as COLOR_0. Unlike .normals these follow
+  /// vertices through deletion and copying. Vertices without one (including
+  /// any added by other operations) are packed as opaque white
+  pub colors: Option<Vec<[f32; 4]>>,
+  
+  /// Point that becomes the local origin when packed. Packing subtracts it
+  /// from every vertex, and adding the packed geometry to a mesh sets the
+  /// translation of the mesh's node to it, so world positions are unchanged
+  /// but the node rotates and scales around the pivot
+  pub pivot: Option<V3<f64>>,
+  
+  /// Smoothing group of each triangle, set by set_smooth_group(). Untagged
+  /// triangles are in group 0. Ignored once triangles are added or removed
+  pub smooth_groups: Option<Vec<u32>>,
+  
+  /// Crease weights of edges, keyed by vertex pair with the smaller index
+  /// first. smooth() holds creased edges sharp, fully at a weight of 1 or
+  /// more. Like .colors these follow vertices through deletion and copying,
+  /// and subdivide() passes them on to both halves of a split edge
+  pub creases: HashMap<(u32, u32), f64>,
+}
+
+impl Default for Geometry {
+  fn default() -> Self {
+    return Self::new();
+  }
 }
 
 impl Geometry {
   /// Raw vertex byffer, suitable for GLTF packing
   pub fn vertices_raw(&self) -> impl Iterator + '_ {
-    self.vertices.iter().flat_map(|v| vec![v[0] as f32, v[1] as f32,
-      v[2] as f32])
+    let pivot = self.pivot.unwrap_or(V3::zeros());
+    
+    self.vertices.iter().map(move |v| v - pivot).flat_map(|v| {
+      vec![v[0] as f32, v[1] as f32, v[2] as f32]
+    })
+  }
+  
+  /// Raw normal buffer, suitable for GLTF packing. GLTF requires unit length
+  /// normals, so they are normalized here
+  pub fn normals_raw(&self) -> impl Iterator + '_ {
+    self.normals.iter().flatten().flat_map(|v| {
+      let v = v.normalize();
+      vec![v[0] as f32, v[1] as f32, v[2] as f32]
+    })
+  }
+  
+  /// Raw texture coordinate buffer, suitable for GLTF packing
+  pub fn uvs_raw(&self) -> impl Iterator + '_ {
+    self.uvs.iter().flatten().flat_map(|v| [v[0] as f32, v[1] as f32])
+  }
+  
+  /// Raw color buffer, suitable for GLTF packing
+  pub fn colors_raw(&self) -> impl Iterator + '_ {
+    (0..self.vertices.len()).flat_map(|i| {
+      self.colors.iter().flat_map(|colors| colors.get(i)).next().copied()
+        .unwrap_or(OPAQUE_WHITE)
+    })
   }
   
   /// Raw triangle byffer, suitable for GLTF packing
@@ -185,6 +436,18 @@ impl Geometry {
     })
   }
   
+  /// Raw edge buffer, suitable for GLTF packing. Uses the same component type
+  /// as the triangle buffer
+  pub fn edges_raw(&self) -> impl Iterator + '_ {
+    self.edges.iter().flat_map(|v| {
+      if self.vertices.len() < 0x10000 {
+        return [v[0] as u16, v[1] as u16].map(u16::to_le_bytes).concat()
+      } else {
+        return [v[0], v[1]].map(u32::to_le_bytes).concat()
+      }
+    })
+  }
+  
   pub fn triangles_raw_component_type(&self) -> ComponentType {
     if self.vertices.len() < 0x10000 {
       ComponentType::UnsignedShort
@@ -193,6 +456,175 @@ impl Geometry {
     }
   }
   
+  /// Errors if adding `count` vertices would take this geometry past the
+  /// limit set by set_max_vertices(). Primitive builders call this before
+  /// generating anything, so huge requests fail cleanly instead of running
+  /// out of memory
+  fn check_vertex_limit(&self, count: u64) -> FFIResult<()> {
+    let max = *lock(&MAX_VERTICES)? as u64;
+    
+    if (self.vertices.len() as u64).saturating_add(count) > max {
+      return Err(ErrorCode::TooManyVertices);
+    }
+    
+    return Ok(());
+  }
+  
+  /// Drops per-vertex data that no longer lines up with .vertices. Called by
+  /// operations that add or remove vertices
+  fn clear_vertex_attributes(&mut self) {
+    self.normals = None;
+    self.uvs = None;
+  }
+  
+  /// Extends .colors over vertices just appended as copies of `sources`, in
+  /// order, so each copy keeps its original's color. Triangle edges already
+  /// using a copy also get the crease weight of the edge they were copied from
+  fn copy_attributes(&mut self, sources: &[u32]) {
+    let start = self.vertices.len() - sources.len();
+    let inherited = self.inherited_creases(|i| {
+      if (i as usize) < start { i } else { sources[i as usize - start] }
+    });
+    self.creases.extend(inherited);
+    
+    let Some(colors) = self.colors.as_mut() else { return };
+    
+    colors.resize(start, OPAQUE_WHITE);
+    for &i in sources {
+      colors.push(colors[i as usize]);
+    }
+  }
+  
+  /// Crease weights for triangle edges whose vertices were copied from a
+  /// creased edge. `source` maps each vertex to the one it was copied from
+  fn inherited_creases(&self, source: impl Fn(u32) -> u32)
+  -> Vec<((u32, u32), f64)> {
+    if self.creases.is_empty() { return Vec::new() };
+    
+    let mut result = Vec::new();
+    for t in &self.triangles {
+      for j in 0..3 {
+        let (a, b) = (t[j], t[(j + 1) % 3]);
+        let (sa, sb) = (source(a), source(b));
+        if let Some(&weight) = self.creases.get(&(sa.min(sb), sa.max(sb))) {
+          result.push(((a.min(b), a.max(b)), weight));
+        }
+      }
+    }
+    
+    return result;
+  }
+  
+  /// Crease weight of the edge between vertices `a` and `b`, or 0 if it has
+  /// none
+  pub fn get_crease(&self, a: u32, b: u32) -> FFIResult<f64> {
+    let count = self.vertices.len() as u32;
+    if a >= count || b >= count { return Err(ErrorCode::VtxOutOfBounds) };
+    
+    return Ok(self.creases.get(&(a.min(b), a.max(b))).copied().unwrap_or(0.0));
+  }
+  
+  pub fn set_vtx_color(&mut self, vertex: u32, color: [f32; 4])
+  -> FFIResult<()> {
+    if vertex as usize >= self.vertices.len() {
+      return Err(ErrorCode::VtxOutOfBounds);
+    }
+    
+    let colors = self.colors.get_or_insert_with(Vec::new);
+    colors.resize(self.vertices.len(), OPAQUE_WHITE);
+    colors[vertex as usize] = color;
+    
+    return Ok(());
+  }
+  
+  /// UVs, if they are populated and line up with .vertices
+  fn uvs_mut(&mut self) -> FFIResult<&mut Vec<[f64; 2]>> {
+    let count = self.vertices.len();
+    
+    return self.uvs.as_mut().filter(|uvs| uvs.len() == count).ok_or(
+      ErrorCode::AttributeMissing);
+  }
+  
+  pub fn get_normal(&self, vertex: u32) -> FFIResult<V3<f64>> {
+    if vertex as usize >= self.vertices.len() {
+      return Err(ErrorCode::VtxOutOfBounds);
+    }
+    
+    let normals = self.normals.as_ref().filter(|normals| {
+      normals.len() == self.vertices.len()
+    }).ok_or(ErrorCode::AttributeMissing)?;
+    
+    return Ok(normals[vertex as usize]);
+  }
+  
+  pub fn get_uv(&self, vertex: u32) -> FFIResult<[f64; 2]> {
+    if vertex as usize >= self.vertices.len() {
+      return Err(ErrorCode::VtxOutOfBounds);
+    }
+    
+    let uvs = self.uvs.as_ref().filter(|uvs| {
+      uvs.len() == self.vertices.len()
+    }).ok_or(ErrorCode::AttributeMissing)?;
+    
+    return Ok(uvs[vertex as usize]);
+  }
+  
+  pub fn set_uv(&mut self, vertex: u32, u: f64, v: f64) -> FFIResult<()> {
+    if vertex as usize >= self.vertices.len() {
+      return Err(ErrorCode::VtxOutOfBounds);
+    }
+    
+    self.uvs_mut()?[vertex as usize] = [u, v];
+    
+    return Ok(());
+  }
+  
+  /// Applies the 2D affine transform [a b; c d]·uv + (tx, ty) to the UVs of
+  /// selected vertices
+  pub fn transform_uv(&mut self, a: f64, b: f64, c: f64, d: f64, tx: f64,
+  ty: f64) -> FFIResult<()> {
+    let selected = self.selected_vertices();
+    let uvs = self.uvs_mut()?;
+    
+    for i in selected {
+      let [u, v] = uvs[i as usize];
+      uvs[i as usize] = [a*u + b*v + tx, c*u + d*v + ty];
+    }
+    
+    return Ok(());
+  }
+  
+  /// Mirrors the UVs of selected vertices about 0.5 on the chosen axes
+  pub fn flip_uv(&mut self, u_axis: bool, v_axis: bool) -> FFIResult<()> {
+    let selected = self.selected_vertices();
+    let uvs = self.uvs_mut()?;
+    
+    for i in selected {
+      let [u, v] = &mut uvs[i as usize];
+      if u_axis { *u = 1.0 - *u };
+      if v_axis { *v = 1.0 - *v };
+    }
+    
+    return Ok(());
+  }
+  
+  /// Scales the UVs of selected vertices about (pivot_u, pivot_v)
+  pub fn scale_uv(&mut self, su: f64, sv: f64, pivot_u: f64, pivot_v: f64)
+  -> FFIResult<()> {
+    let selected = self.selected_vertices();
+    let uvs = self.uvs_mut()?;
+    
+    for i in selected {
+      let [u, v] = uvs[i as usize];
+      uvs[i as usize] = [
+        pivot_u + su*(u - pivot_u),
+        pivot_v + sv*(v - pivot_v),
+      ];
+    }
+    
+    return Ok(());
+  }
+  
   // Apply a translation
   pub fn t(&mut self, x: f64, y: f64, z: f64) -> &mut Self {
     let translation = V3::new(x, y, z);
@@ -215,1082 +647,6488 @@ impl Geometry {
     self
   }
   
-  // rotations / matrices
-  
-  // Merges
+  /// Rotates selected vertices about `pivot`, by `x` radians around the X
+  /// axis, then `y` around Y, then `z` around Z
+  pub fn rotate_euler_about(&mut self, pivot: V3<f64>, x: f64, y: f64,
+  z: f64) -> FFIResult<()> {
+    let rotation = Rotation3::from_euler_angles(x, y, z);
+    
+    return self.apply_about(pivot, rotation.into_inner());
+  }
   
-  // Vertex deduplication
+  /// Scales selected vertices about `pivot`
+  pub fn scale_about(&mut self, pivot: V3<f64>, x: f64, y: f64, z: f64)
+  -> FFIResult<()> {
+    return self.apply_about(pivot, Matrix3::from_diagonal(&V3::new(x, y, z)));
+  }
   
-  /// Returns a list of vertices within the bounding box defined by the given
-  /// points. Allows error of 1e-6
-  pub fn select_vertices(&mut self, bound_1: V3<f64>, bound_2: V3<f64>) {
-    self.selection.drain(..);
-    self.selection_type = SelectionType::VERTICES;
+  fn apply_about(&mut self, pivot: V3<f64>, linear: Matrix3<f64>)
+  -> FFIResult<()> {
+    let matrix = Matrix4::new_translation(&pivot)*linear.to_homogeneous()*
+      Matrix4::new_translation(&-pivot);
     
-    let lower_bound = bound_1.inf(&bound_2) - V3::new(1e-6, 1e-6, 1e-6);
-    let upper_bound = bound_1.sup(&bound_2) + V3::new(1e-6, 1e-6, 1e-6);
+    return self.apply_matrix(&matrix);
+  }
+  
+  /// Applies a column-major 4x4 matrix to the selected vertices. Triangles
+  /// among them are reversed if the matrix mirrors, so they keep facing
+  /// outward, and normals are carried through the inverse transpose. Fails
+  /// without changing anything if a vertex would land at infinity (w = 0)
+  pub fn transform_matrix(&mut self, m: [f64; 16]) -> FFIResult<()> {
+    return self.apply_matrix(&Matrix4::from_column_slice(&m));
+  }
+  
+  fn apply_matrix(&mut self, matrix: &Matrix4<f64>) -> FFIResult<()> {
+    let selected = self.selected_vertices();
     
-    for i in 0..self.vertices.len() {
-      if lower_bound[0] < self.vertices[i][0] &&
-         self.vertices[i][0] < upper_bound[0] &&
-         lower_bound[1] < self.vertices[i][1] &&
-         self.vertices[i][1] < upper_bound[1] &&
-         lower_bound[2] < self.vertices[i][2] &&
-         self.vertices[i][2] < upper_bound[2] {
-        self.selection.push(i as u32);
+    let mut positions = Vec::with_capacity(selected.len());
+    for &i in &selected {
+      let h = matrix*Point3::from(self.vertices[i as usize]).to_homogeneous();
+      if h.w == 0.0 || !h.w.is_finite() {
+        return Err(ErrorCode::ParameterOutOfRange);
       }
+      positions.push(h.xyz()/h.w);
+    }
+    for (&i, position) in selected.iter().zip(positions) {
+      self.vertices[i as usize] = position;
     }
-  }
-  
-  /// Returns a list of triangles within the bounding box defined by the given
-  /// points. Allows error of 1e-6
-  pub fn select_triangles(&mut self, bound_1: V3<f64>, bound_2: V3<f64>) {
-    self.select_vertices(bound_1, bound_2);
-    let bounded_vertices = self.selection.clone();
     
-    self.selection.drain(..);
-    self.selection_type = SelectionType::TRIANGLES;
+    let linear = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+    let normal_matrix = linear.try_inverse().map(|m| m.transpose());
+    if let Some(normals) = &mut self.normals {
+      match normal_matrix {
+        Some(normal_matrix) => for &i in &selected {
+          let normal = &mut normals[i as usize];
+          *normal = (normal_matrix**normal).try_normalize(0.0)
+            .unwrap_or(*normal);
+        },
+        None => self.normals = None,
+      }
+    }
     
-    for i in 0..self.triangles.len() {
-      if bounded_vertices.contains(&self.triangles[i][0]) &&
-         bounded_vertices.contains(&self.triangles[i][1]) &&
-         bounded_vertices.contains(&self.triangles[i][2]) {
-        self.selection.push(i as u32);
+    if linear.determinant() < 0.0 {
+      let mut is_selected = vec![false; self.vertices.len()];
+      for &i in &selected { is_selected[i as usize] = true };
+      
+      for triangle in &mut self.triangles {
+        if triangle.iter().all(|&i| is_selected[i as usize]) {
+          triangle.swap(1, 2);
+        }
       }
     }
+    
+    return Ok(());
   }
   
-  /// Automatically deletes affected triangles
-  pub fn delete_vertex(&mut self, vertex: u32) {
-    // Swap remove to avoid having to shift vertices
-    self.vertices.swap_remove(vertex as usize);
-    let swapped_vertex = self.vertices.len() as u32;
+  /// Copies the selected vertices, with the triangles and edges among them,
+  /// so there are `count` in total (including the original) spaced evenly
+  /// through a full turn about `axis` through the origin. Selection becomes
+  /// the original and every copy
+  pub fn array_radial(&mut self, count: u32, axis: V3<f64>) -> FFIResult<()> {
+    if count == 0 { return Err(ErrorCode::ParameterOutOfRange) };
+    let axis = Unit::try_new(axis, 0.0).ok_or(ErrorCode::ParameterOutOfRange)?;
     
-    for i in 0..self.triangles.len() {
-      // Delete triangle if it includes deleted vertex
-      if self.triangles[i].contains(&vertex) {
-        self.triangles.swap_remove(i);
-        continue;
-      }
+    let selected = self.selected_vertices();
+    self.check_vertex_limit((count as u64 - 1).saturating_mul(
+      selected.len() as u64))?;
+    
+    let mut position: Vec<Option<u32>> = vec![None; self.vertices.len()];
+    for (i, &v) in selected.iter().enumerate() {
+      position[v as usize] = Some(i as u32);
+    }
+    let triangles: Vec<[u32; 3]> = self.triangles.iter().filter_map(|t| {
+      Some([position[t[0] as usize]?, position[t[1] as usize]?,
+        position[t[2] as usize]?])
+    }).collect();
+    let edges: Vec<[u32; 2]> = self.edges.iter().filter_map(|e| {
+      Some([position[e[0] as usize]?, position[e[1] as usize]?])
+    }).collect();
+    
+    let offset = self.vertices.len() as u32;
+    for k in 1..count {
+      let rotation = Rotation3::from_axis_angle(&axis,
+        2.0*std::f64::consts::PI*k as f64/count as f64);
+      let base = self.vertices.len() as u32;
       
-      // Update indices if swapped vertex is referenced
-      for j in 0..2 {
-        if self.triangles[i][j] == swapped_vertex {
-          self.triangles[i][j] = vertex
-        }
+      for &v in &selected {
+        self.vertices.push(rotation*self.vertices[v as usize]);
       }
+      self.triangles.extend(triangles.iter().map(|t| t.map(|i| base + i)));
+      self.edges.extend(edges.iter().map(|e| e.map(|i| base + i)));
     }
+    self.copy_attributes(&selected.repeat(count as usize - 1));
+    self.clear_vertex_attributes();
     
     self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(&selected);
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
   }
   
-  /// Automatically deletes affected triangles
-  pub fn delete_vertices(&mut self) {
-    // Vertices must be processed in reverse order, because deletion of lower-
-    // index vertices can change the index of higher-index vertices
-    self.selection.sort_unstable();
-    self.selection.reverse();
+  /// Mean position of the selected vertices, or None if nothing is selected
+  pub fn selection_centroid(&self) -> Option<V3<f64>> {
+    let selected = self.selected_vertices();
+    if selected.is_empty() { return None };
     
-    for vertex in self.selection.clone() {
-      self.delete_vertex(vertex);
+    return Some(selected.iter().fold(V3::zeros(), |sum, &i| {
+      sum + self.vertices[i as usize]
+    })/(selected.len() as f64));
+  }
+  
+  /// Indices of selected vertices. If triangles are selected, this is every
+  /// vertex used by a selected triangle
+  pub fn selected_vertices(&self) -> Vec<u32> {
+    match self.selection_type {
+      SelectionType::VERTICES => self.selection.clone(),
+      SelectionType::TRIANGLES => {
+        let mut result: Vec<u32> = self.selection.iter().flat_map(|&i| {
+          self.triangles[i as usize]
+        }).collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+      },
     }
   }
   
-  pub fn delete_triangle(&mut self, triangle: u32) {
-    self.triangles.swap_remove(triangle as usize);
-    self.selection.drain(..);
+  /// Indices of selected triangles. If vertices are selected, this is every
+  /// triangle whose three vertices are all selected
+  pub fn selected_triangles(&self) -> Vec<u32> {
+    match self.selection_type {
+      SelectionType::TRIANGLES => self.selection.clone(),
+      SelectionType::VERTICES => {
+        let mut selected = vec![false; self.vertices.len()];
+        for &i in &self.selection {
+          selected[i as usize] = true;
+        }
+        
+        (0..self.triangles.len() as u32).filter(|&i| {
+          self.triangles[i as usize].iter().all(|&j| selected[j as usize])
+        }).collect()
+      },
+    }
   }
   
-  pub fn delete_triangles(&mut self) {
-    // Triangles must be processed in reverse order, because deletion of lower-
-    // index vertices can change the index of higher-index vertices
-    self.selection.sort_unstable();
-    self.selection.reverse();
+  /// Switches between vertex and triangle selection, converting the current
+  /// selection. Going from vertices to triangles keeps only triangles whose
+  /// three vertices were all selected
+  pub fn set_selection_mode(&mut self, mode: SelectionType) {
+    self.selection = match mode {
+      SelectionType::VERTICES => self.selected_vertices(),
+      SelectionType::TRIANGLES => self.selected_triangles(),
+    };
+    self.selection_type = mode;
+  }
+  
+  /// Unit normal of a triangle following its winding, or zero if the
+  /// triangle has no area
+  fn face_normal(&self, triangle: usize) -> V3<f64> {
+    let [a, b, c] = self.triangles[triangle].map(|i| self.vertices[i as usize]);
     
-    for triangle in self.selection.clone() {
-      self.delete_triangle(triangle);
+    return (b - a).cross(&(c - a)).try_normalize(0.0).unwrap_or(V3::zeros());
+  }
+  
+  pub fn triangle_area(&self, triangle: usize) -> f64 {
+    let [a, b, c] = self.triangles[triangle].map(|i| self.vertices[i as usize]);
+    
+    return (b - a).cross(&(c - a)).norm()/2.0;
+  }
+  
+  /// Radius ratio of a triangle: twice the inscribed radius over the
+  /// circumscribed radius, computed from the side lengths as
+  /// (b + c - a)(c + a - b)(a + b - c)/(abc). 1 for an equilateral triangle,
+  /// approaching 0 for slivers
+  pub fn triangle_quality(&self, triangle: usize) -> f64 {
+    let [p, q, r] = self.triangles[triangle].map(|i| self.vertices[i as usize]);
+    let (a, b, c) = ((q - r).norm(), (r - p).norm(), (p - q).norm());
+    
+    let product = a*b*c;
+    if product == 0.0 { return 0.0 };
+    
+    return ((b + c - a)*(c + a - b)*(a + b - c)/product).max(0.0);
+  }
+  
+  /// The triangle sharing edge `edge` (from corner `edge` to the next corner)
+  /// of `triangle`, or None if that edge is open. Where more than two
+  /// triangles meet, the lowest-numbered other one is returned
+  pub fn triangle_neighbor(&self, triangle: u32, edge: usize)
+  -> FFIResult<Option<u32>> {
+    if triangle as usize >= self.triangles.len() {
+      return Err(ErrorCode::TriOutOfBounds);
     }
+    if edge >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let t = self.triangles[triangle as usize];
+    let (a, b) = (t[edge], t[(edge + 1) % 3]);
+    
+    return Ok((0..self.triangles.len() as u32).find(|&i| {
+      i != triangle && self.triangles[i as usize].contains(&a) &&
+        self.triangles[i as usize].contains(&b)
+    }));
   }
   
-  pub fn delete_stray_vertices(&mut self) {
-    // Vertices must be processed in reverse order, because deletion of lower-
-    // index vertices can change the index of higher-index vertices
-    for vertex in self.vertices.len()..0 {
-      let mut vertex_used = false;
-      for triangle in &self.triangles {
-        if triangle.contains(&(vertex as u32)) {
-          vertex_used = true;
-        }
-      }
-      
-      if vertex_used {
-        self.delete_vertex(vertex as u32);
-      }
+  /// Selects vertices of every triangle whose area is within `tolerance` (as
+  /// a fraction) of the reference triangle's area
+  pub fn select_similar_area(&mut self, reference_tri: u32, tolerance: f64)
+  -> FFIResult<()> {
+    if reference_tri as usize >= self.triangles.len() {
+      return Err(ErrorCode::TriOutOfBounds);
     }
+    
+    let reference = self.triangle_area(reference_tri as usize);
+    
+    let mut selection: Vec<u32> = (0..self.triangles.len()).filter(|&i| {
+      (self.triangle_area(i) - reference).abs() <= tolerance*reference
+    }).flat_map(|i| self.triangles[i]).collect();
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+    
+    return Ok(());
   }
   
-  pub fn cube() -> Self {
-    Self {
-      vertices: vec![
-        V3::new(-1.0,  1.0, -1.0),
-        V3::new(-1.0,  1.0,  1.0),
-        
-        V3::new(-1.0, -1.0, -1.0),
-        V3::new(-1.0, -1.0,  1.0),
-        
-        V3::new( 1.0,  1.0, -1.0),
-        V3::new( 1.0,  1.0,  1.0),
-        
-        V3::new( 1.0, -1.0, -1.0),
-        V3::new( 1.0, -1.0,  1.0),
-      ],
-      triangles: vec![
-        // Top
-        [1, 3, 5],
-        [3, 7, 5],
-        
-        // +X side
-        [4, 5, 6],
-        [5, 7, 6],
+  /// Whether a vertex is selected, either directly or as part of a selected
+  /// triangle
+  pub fn is_selected(&self, vertex: u32) -> bool {
+    match self.selection_type {
+      SelectionType::VERTICES => self.selection.contains(&vertex),
+      SelectionType::TRIANGLES => self.selection.iter().any(|&i| {
+        self.triangles[i as usize].contains(&vertex)
+      }),
+    }
+  }
+  
+  /// Scales selected vertices toward the given axis, by a factor looked up
+  /// from `samples`. The first sample applies at `axis_min` and the last at
+  /// `axis_max`, with linear interpolation in between. Vertices outside that
+  /// range use the nearest end sample
+  pub fn taper_profile(&mut self, axis: u8, samples: &[f64], axis_min: f64,
+  axis_max: f64) -> FFIResult<()> {
+    if axis >= 3 || samples.len() < 2 || axis_min == axis_max {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    
+    let axis = axis as usize;
+    let last = (samples.len() - 1) as f64;
+    
+    for i in self.selected_vertices() {
+      let vertex = &mut self.vertices[i as usize];
+      
+      let position = ((vertex[axis] - axis_min)/(axis_max - axis_min))
+        .clamp(0.0, 1.0)*last;
+      let index = (position.floor() as usize).min(samples.len() - 2);
+      let fraction = position - index as f64;
+      let factor = samples[index]*(1.0 - fraction) +
+        samples[index + 1]*fraction;
+      
+      for j in 0..3 {
+        if j != axis {
+          vertex[j] *= factor;
+        }
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Rounds each coordinate of selected vertices to the nearest multiple of
+  /// `step`. Removes float drift and makes nearly-coincident vertices exactly
+  /// equal
+  pub fn quantize(&mut self, step: f64) -> FFIResult<()> {
+    if step <= 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    for i in self.selected_vertices() {
+      self.vertices[i as usize] = self.vertices[i as usize]
+        .map(|value| (value/step).round()*step);
+    }
+    
+    return Ok(());
+  }
+  
+  /// Moves selected vertices lying within tolerance of the plane axis = value
+  /// exactly onto it
+  pub fn snap_to_plane(&mut self, axis: u8, value: f64, tolerance: f64)
+  -> FFIResult<()> {
+    if axis >= 3 || tolerance < 0.0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    
+    let axis = axis as usize;
+    for i in self.selected_vertices() {
+      let vertex = &mut self.vertices[i as usize];
+      if (vertex[axis] - value).abs() <= tolerance {
+        vertex[axis] = value;
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Axis-aligned bounding box as (min, max) corners, or None if there are no
+  /// vertices
+  pub fn aabb(&self) -> Option<(V3<f64>, V3<f64>)> {
+    let first = *self.vertices.first()?;
+    
+    return Some(self.vertices.iter().fold((first, first), |(min, max), v| {
+      (min.inf(v), max.sup(v))
+    }));
+  }
+  
+  /// Bounding box of the selected vertices as (min, max) corners, or None if
+  /// nothing is selected
+  pub fn selection_aabb(&self) -> Option<(V3<f64>, V3<f64>)> {
+    let selected = self.selected_vertices();
+    let first = self.vertices[*selected.first()? as usize];
+    
+    return Some(selected.iter().fold((first, first), |(min, max), &i| {
+      (min.inf(&self.vertices[i as usize]), max.sup(&self.vertices[i as usize]))
+    }));
+  }
+  
+  /// Index of the vertex nearest to a point, or None if there are no vertices
+  pub fn closest_vertex(&self, point: V3<f64>) -> Option<u32> {
+    return (0..self.vertices.len()).min_by(|&a, &b| {
+      (self.vertices[a] - point).norm_squared().total_cmp(
+        &(self.vertices[b] - point).norm_squared())
+    }).map(|i| i as u32);
+  }
+  
+  /// Selects, for each selected vertex in `source`, the nearest vertex in this
+  /// geometry if one is within `threshold`
+  pub fn transfer_selection(&mut self, source: &Geometry, threshold: f64)
+  -> FFIResult<()> {
+    if threshold <= 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let grid = SpatialGrid::new(&self.vertices, threshold);
+    let mut selection = Vec::new();
+    
+    for i in source.selected_vertices() {
+      let point = source.vertices[i as usize];
+      
+      let nearest = grid.nearby(&point).map(|j| {
+        (j, (self.vertices[j as usize] - point).norm())
+      }).filter(|&(_, distance)| distance <= threshold)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+      
+      if let Some((j, _)) = nearest {
+        selection.push(j);
+      }
+    }
+    
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+    
+    return Ok(());
+  }
+  
+  /// Principal axes of the vertex cloud, from the eigenvectors of its
+  /// covariance matrix. Sorted from the axis of greatest spread to least, and
+  /// always a right-handed orthonormal basis, even for symmetric or empty
+  /// geometries
+  pub fn principal_axes(&self) -> [V3<f64>; 3] {
+    if self.vertices.is_empty() { return [V3::x(), V3::y(), V3::z()] };
+    
+    let count = self.vertices.len() as f64;
+    let mean = self.vertices.iter().sum::<V3<f64>>()/count;
+    let covariance = self.vertices.iter().fold(Matrix3::zeros(), |sum, v| {
+      sum + (v - mean)*(v - mean).transpose()
+    })/count;
+    
+    let eigen = covariance.symmetric_eigen();
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| {
+      eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a])
+    });
+    
+    let first: V3<f64> = eigen.eigenvectors.column(order[0]).normalize();
+    let second: V3<f64> = eigen.eigenvectors.column(order[1]).normalize();
+    
+    return [first, second, first.cross(&second)];
+  }
+  
+  /// Centers the bounding box on the origin and scales uniformly so the
+  /// longest axis spans -1 to 1. Does nothing to an empty geometry
+  pub fn normalize(&mut self) {
+    let Some((min, max)) = self.aabb() else { return };
+    
+    let center = (min + max)/2.0;
+    let extent = (max - min).max();
+    let scale = if extent > 0.0 { 2.0/extent } else { 1.0 };
+    
+    for vertex in &mut self.vertices {
+      *vertex = (*vertex - center)*scale;
+    }
+  }
+  
+  /// Puts the selected triangles in a smoothing group. compute_normals() only
+  /// averages across triangles in the same group
+  pub fn set_smooth_group(&mut self, group: u32) {
+    let count = self.triangles.len();
+    let selected = self.selected_triangles();
+    
+    let groups = self.smooth_groups.get_or_insert_with(Vec::new);
+    if groups.len() != count {
+      *groups = vec![0; count];
+    }
+    for i in selected {
+      groups[i as usize] = group;
+    }
+  }
+  
+  /// Sets the crease weight of every triangle edge with both ends selected.
+  /// A weight of 0 removes the crease
+  pub fn set_crease(&mut self, weight: f64) -> FFIResult<()> {
+    if weight.is_nan() || weight < 0.0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    
+    let mut selected = vec![false; self.vertices.len()];
+    for i in self.selected_vertices() {
+      selected[i as usize] = true;
+    }
+    
+    for t in &self.triangles {
+      for j in 0..3 {
+        let (a, b) = (t[j], t[(j + 1) % 3]);
+        if !selected[a as usize] || !selected[b as usize] { continue };
         
-        // -X side
-        [0, 2, 1],
-        [1, 2, 3],
+        if weight == 0.0 {
+          self.creases.remove(&(a.min(b), a.max(b)));
+        } else {
+          self.creases.insert((a.min(b), a.max(b)), weight);
+        }
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Gives each smoothing group its own copy of vertices it shares with
+  /// another group, so the groups can have separate normals. UVs are copied
+  /// along with the vertices
+  fn split_smooth_groups(&mut self) {
+    let Some(groups) = self.smooth_groups.as_ref().filter(|groups| {
+      groups.len() == self.triangles.len()
+    }) else { return };
+    
+    let mut owner: Vec<Option<u32>> = vec![None; self.vertices.len()];
+    let mut copies: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut sources = Vec::new();
+    let mut uvs = self.uvs.take().filter(|uvs| {
+      uvs.len() == self.vertices.len()
+    });
+    
+    for (triangle, &group) in self.triangles.iter_mut().zip(groups) {
+      for index in triangle.iter_mut() {
+        let vertex = *index as usize;
         
-        // +Y side
-        [0, 1, 4],
-        [1, 5, 4],
+        match owner[vertex] {
+          None => owner[vertex] = Some(group),
+          Some(first) if first == group => {},
+          Some(_) => {
+            *index = *copies.entry((*index, group)).or_insert_with(|| {
+              self.vertices.push(self.vertices[vertex]);
+              if let Some(uvs) = uvs.as_mut() { uvs.push(uvs[vertex]) };
+              sources.push(vertex as u32);
+              (self.vertices.len() - 1) as u32
+            });
+          },
+        }
+      }
+    }
+    
+    self.uvs = uvs;
+    self.copy_attributes(&sources);
+  }
+  
+  /// Computes vertex normals by averaging the normals of the triangles around
+  /// each vertex. Vertices not used by any triangle get +Z. If smoothing
+  /// groups are set, vertices shared between groups are split first
+  pub fn compute_normals(&mut self, weighting: NormalWeighting) {
+    self.split_smooth_groups();
+    
+    let mut normals = vec![V3::zeros(); self.vertices.len()];
+    
+    for triangle in &self.triangles {
+      let corners = triangle.map(|i| self.vertices[i as usize]);
+      
+      // Length of the cross product is twice the triangle's area
+      let cross = (corners[1] - corners[0]).cross(&(corners[2] - corners[0]));
+      let Some(unit) = cross.try_normalize(0.0) else { continue };
+      
+      for j in 0..3 {
+        let weight = match weighting {
+          NormalWeighting::Uniform => 1.0,
+          NormalWeighting::Area => cross.norm()/2.0,
+          NormalWeighting::Angle => (corners[(j + 1) % 3] - corners[j])
+            .angle(&(corners[(j + 2) % 3] - corners[j])),
+        };
         
-        // -Y side
-        [2, 6, 3],
-        [3, 6, 7],
+        normals[triangle[j] as usize] += unit*weight;
+      }
+    }
+    
+    self.normals = Some(normals.into_iter().map(|normal| {
+      normal.try_normalize(0.0).unwrap_or(V3::z())
+    }).collect());
+  }
+  
+  /// Gives every triangle three vertices of its own, so the vertex count
+  /// becomes 3× the triangle count (plus any vertices used only by edges).
+  /// Normals and UVs are copied to the new vertices. Selects all vertices
+  pub fn split_faces(&mut self) {
+    let count = self.vertices.len();
+    let normals = self.normals.take().filter(|normals| normals.len() == count);
+    let uvs = self.uvs.take().filter(|uvs| uvs.len() == count);
+    let colors = self.colors.take().map(|mut colors| {
+      colors.resize(count, OPAQUE_WHITE);
+      colors
+    });
+    let old_vertices = std::mem::take(&mut self.vertices);
+    
+    let mut vertices = Vec::with_capacity(self.triangles.len()*3);
+    let mut sources = Vec::with_capacity(self.triangles.len()*3);
+    for triangle in &mut self.triangles {
+      for index in triangle.iter_mut() {
+        vertices.push(old_vertices[*index as usize]);
+        sources.push(*index as usize);
+        *index = (vertices.len() - 1) as u32;
+      }
+    }
+    
+    let mut edge_copies: HashMap<u32, u32> = HashMap::new();
+    for index in self.edges.iter_mut().flatten() {
+      *index = *edge_copies.entry(*index).or_insert_with(|| {
+        vertices.push(old_vertices[*index as usize]);
+        sources.push(*index as usize);
+        (vertices.len() - 1) as u32
+      });
+    }
+    
+    self.vertices = vertices;
+    self.clear_vertex_attributes();
+    self.creases = self.inherited_creases(|i| sources[i as usize] as u32)
+      .into_iter().collect();
+    self.normals = normals.map(|normals| {
+      sources.iter().map(|&i| normals[i]).collect()
+    });
+    self.uvs = uvs.map(|uvs| sources.iter().map(|&i| uvs[i]).collect());
+    self.colors = colors.map(|colors| {
+      sources.iter().map(|&i| colors[i]).collect()
+    });
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(0..self.vertices.len() as u32);
+  }
+  
+  /// Flat shading: splits faces, then gives each vertex its triangle's normal
+  pub fn compute_flat_normals(&mut self) {
+    self.split_faces();
+    self.compute_normals(NormalWeighting::Uniform);
+  }
+  
+  /// Computes area-weighted normals, but only smooths across edges whose
+  /// faces meet at less than `max_angle` radians. Vertices on sharper edges
+  /// are split so each side keeps its own normal, so the vertex count can
+  /// grow. UVs are copied to the new vertices
+  pub fn compute_normals_with_angle(&mut self, max_angle: f64) {
+    self.split_smooth_groups();
+    
+    // Length of the cross product is twice the triangle's area
+    let faces: Vec<V3<f64>> = self.triangles.iter().map(|triangle| {
+      let corners = triangle.map(|i| self.vertices[i as usize]);
+      (corners[1] - corners[0]).cross(&(corners[2] - corners[0]))
+    }).collect();
+    
+    let mut edge_triangles: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (i, triangle) in self.triangles.iter().enumerate() {
+      for j in 0..3 {
+        let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+        edge_triangles.entry((a.min(b), a.max(b))).or_default().push(i);
+      }
+    }
+    
+    // Corners are numbered triangle*3 + position in triangle. Corners joined
+    // across smooth edges end up sharing a vertex
+    let mut parent: Vec<usize> = (0..self.triangles.len()*3).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+      while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+      }
+      return i;
+    }
+    let corner = |triangle: usize, vertex: u32| triangle*3 +
+      self.triangles[triangle].iter().position(|&i| i == vertex).unwrap();
+    for (&(a, b), triangles) in &edge_triangles {
+      let &[t1, t2] = triangles.as_slice() else { continue };
+      if faces[t1].angle(&faces[t2]) >= max_angle { continue };
+      
+      for vertex in [a, b] {
+        let c1 = root(&mut parent, corner(t1, vertex));
+        let c2 = root(&mut parent, corner(t2, vertex));
+        parent[c1.max(c2)] = c1.min(c2);
+      }
+    }
+    
+    let mut owner: Vec<Option<usize>> = vec![None; self.vertices.len()];
+    let mut copies: HashMap<usize, u32> = HashMap::new();
+    let mut sources = Vec::new();
+    let mut uvs = self.uvs.take().filter(|uvs| {
+      uvs.len() == self.vertices.len()
+    });
+    
+    for i in 0..self.triangles.len() {
+      for j in 0..3 {
+        let class = root(&mut parent, i*3 + j);
+        let vertex = self.triangles[i][j] as usize;
         
-        // Bottom
-        [0, 4, 2],
-        [2, 4, 6],
-      ],
-      selection: Vec::new(),
-      selection_type: SelectionType::VERTICES,
+        match owner[vertex] {
+          None => owner[vertex] = Some(class),
+          Some(first) if first == class => {},
+          Some(_) => {
+            self.triangles[i][j] = *copies.entry(class).or_insert_with(|| {
+              self.vertices.push(self.vertices[vertex]);
+              if let Some(uvs) = uvs.as_mut() { uvs.push(uvs[vertex]) };
+              sources.push(vertex as u32);
+              (self.vertices.len() - 1) as u32
+            });
+          },
+        }
+      }
     }
+    
+    self.uvs = uvs;
+    self.copy_attributes(&sources);
+    
+    let mut normals = vec![V3::zeros(); self.vertices.len()];
+    for (triangle, face) in self.triangles.iter().zip(&faces) {
+      for &i in triangle {
+        normals[i as usize] += face;
+      }
+    }
+    
+    self.normals = Some(normals.into_iter().map(|normal| {
+      normal.try_normalize(0.0).unwrap_or(V3::z())
+    }).collect());
   }
   
-  pub fn pack(&self, gltf: &mut GLTF) -> PackedGeometry {
-    // Calculate vertex bounds. The vertex bounds are f32 because that is the
-    // same precision as GLTF vertices
-    let mut min = V3::repeat(f32::MAX);
-    let mut max = V3::repeat(f32::MIN);
-    for vertex in &self.vertices {
-      let vertex = V3::new(vertex.x as f32, vertex.y as f32, vertex.z as f32);
-      min = min.inf(&vertex);
-      max = max.sup(&vertex);
+  /// Edges used by exactly one of the given triangles, in the direction the
+  /// triangle traverses them
+  fn boundary_edges(&self, triangles: &[u32]) -> Vec<[u32; 2]> {
+    let uses = self.edge_uses(triangles);
+    
+    let mut result = Vec::new();
+    for &i in triangles {
+      let triangle = self.triangles[i as usize];
+      for j in 0..3 {
+        let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+        if uses[&(a.min(b), a.max(b))] == 1 {
+          result.push([a, b]);
+        }
+      }
     }
     
-    gltf.append_to_glb_bin(self.vertices_raw(), Type::VEC3,
-      ComponentType::Float);
-    // Can .unwrap() because the previous .append_to_glb_bin() call guarantees
-    // .accessors/min/max will be populated
-    gltf.accessors.last_mut().unwrap().min.extend_from_slice(min.as_slice());
-    gltf.accessors.last_mut().unwrap().max.extend_from_slice(max.as_slice());
-    gltf.buffer_views.last_mut().unwrap().target = Some(
-      Target::ArrayBuffer);
+    return result;
+  }
+  
+  /// Boundary of the given triangles as one loop of vertex indices, following
+  /// the triangles' winding. None if the boundary is not a single simple loop
+  fn outline(&self, triangles: &[u32]) -> Option<Vec<u32>> {
+    let edges = self.boundary_edges(triangles);
     
-    gltf.append_to_glb_bin(self.triangles_raw(), Type::SCALAR,
-      self.triangles_raw_component_type());
-    gltf.buffer_views.last_mut().unwrap().target = Some(
-      Target::ElementArrayBuffer);
+    let mut next: HashMap<u32, u32> = HashMap::new();
+    for &[a, b] in &edges {
+      if next.insert(a, b).is_some() { return None };
+    }
     
-    return PackedGeometry {
-      vertex_buffer: gltf.accessors.len() as u32 - 2,
-      triangle_buffer: gltf.accessors.len() as u32 - 1,
+    let start = edges.first()?[0];
+    let mut result = vec![start];
+    let mut current = next[&start];
+    while current != start {
+      if result.len() >= edges.len() { return None };
+      result.push(current);
+      current = *next.get(&current)?;
+    }
+    
+    return (result.len() == edges.len()).then_some(result);
+  }
+  
+  /// Number of the given triangles using each undirected edge, keyed by
+  /// (lower index, higher index)
+  fn edge_uses(&self, triangles: &[u32]) -> HashMap<(u32, u32), u32> {
+    let mut uses: HashMap<(u32, u32), u32> = HashMap::new();
+    for &i in triangles {
+      let triangle = self.triangles[i as usize];
+      for j in 0..3 {
+        let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+        *uses.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+      }
+    }
+    
+    return uses;
+  }
+  
+  /// Selects every vertex on an edge used by only one triangle. On a closed
+  /// mesh there are no such edges, so the selection becomes empty
+  pub fn select_boundary(&mut self) {
+    let all: Vec<u32> = (0..self.triangles.len() as u32).collect();
+    
+    let mut selection: Vec<u32> = self.boundary_edges(&all).into_iter()
+      .flatten().collect();
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+  }
+  
+  /// Vertices sharing a triangle edge with each vertex, without repeats
+  fn vertex_neighbors(&self) -> Vec<Vec<u32>> {
+    let mut neighbors: Vec<Vec<u32>> = vec![Vec::new(); self.vertices.len()];
+    for triangle in &self.triangles {
+      for j in 0..3 {
+        let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+        neighbors[a as usize].push(b);
+        neighbors[b as usize].push(a);
+      }
+    }
+    for list in &mut neighbors {
+      list.sort_unstable();
+      list.dedup();
+    }
+    
+    return neighbors;
+  }
+  
+  /// Adds every vertex sharing a triangle edge with a selected vertex, growing
+  /// the selection by one ring. If triangles are selected, their vertices
+  /// count as selected
+  pub fn select_grow(&mut self) {
+    let neighbors = self.vertex_neighbors();
+    
+    let mut selection = self.selected_vertices();
+    for i in selection.clone() {
+      selection.extend(&neighbors[i as usize]);
+    }
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+  }
+  
+  /// Deselects every selected vertex that shares a triangle edge with an
+  /// unselected vertex, shrinking the selection by one ring
+  pub fn select_shrink(&mut self) {
+    let neighbors = self.vertex_neighbors();
+    
+    let mut selected = vec![false; self.vertices.len()];
+    for i in self.selected_vertices() {
+      selected[i as usize] = true;
+    }
+    
+    self.selection = (0..self.vertices.len() as u32).filter(|&i| {
+      selected[i as usize] &&
+      neighbors[i as usize].iter().all(|&j| selected[j as usize])
+    }).collect();
+    self.selection_type = SelectionType::VERTICES;
+  }
+  
+  /// Selects the vertices of every triangle whose normal is within
+  /// `max_angle` radians of `direction`. Triangles with no area are skipped
+  pub fn select_by_normal(&mut self, direction: V3<f64>, max_angle: f64)
+  -> FFIResult<()> {
+    let direction = direction.try_normalize(0.0).ok_or(
+      ErrorCode::ParameterOutOfRange)?;
+    
+    let mut selection: Vec<u32> = (0..self.triangles.len()).filter(|&i| {
+      let normal = self.face_normal(i);
+      normal != V3::zeros() && normal.angle(&direction) <= max_angle
+    }).flat_map(|i| self.triangles[i]).collect();
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+    
+    return Ok(());
+  }
+  
+  /// Extends the selection to every vertex connected to it through triangle
+  /// edges, so each piece it touches becomes fully selected
+  pub fn select_linked(&mut self) {
+    let neighbors = self.vertex_neighbors();
+    
+    let mut selected = vec![false; self.vertices.len()];
+    let mut queue = self.selected_vertices();
+    for &i in &queue {
+      selected[i as usize] = true;
+    }
+    
+    while let Some(i) = queue.pop() {
+      for &j in &neighbors[i as usize] {
+        if !selected[j as usize] {
+          selected[j as usize] = true;
+          queue.push(j);
+        }
+      }
+    }
+    
+    self.selection = (0..self.vertices.len() as u32).filter(|&i| {
+      selected[i as usize]
+    }).collect();
+    self.selection_type = SelectionType::VERTICES;
+  }
+  
+  /// Selects every vertex that is not selected. If triangles are selected,
+  /// their vertices count as selected
+  pub fn select_invert(&mut self) {
+    let mut selected = vec![false; self.vertices.len()];
+    for i in self.selected_vertices() {
+      selected[i as usize] = true;
+    }
+    
+    self.selection = (0..self.vertices.len() as u32).filter(|&i| {
+      !selected[i as usize]
+    }).collect();
+    self.selection_type = SelectionType::VERTICES;
+  }
+  
+  /// Selects the given vertices. Repeated indices are selected once
+  pub fn select_vertex_list(&mut self, vertices: &[u32]) -> FFIResult<()> {
+    if vertices.iter().any(|&i| i as usize >= self.vertices.len()) {
+      return Err(ErrorCode::VtxOutOfBounds);
+    }
+    
+    let mut selection = vertices.to_vec();
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+    
+    return Ok(());
+  }
+  
+  /// Selects the vertices of crease edges. Each edge between two triangles
+  /// has a dihedral angle (0 where they are flat), and the background bending
+  /// around each vertex is the length-weighted mean dihedral angle of its
+  /// edges below `min_angle`. An edge is a crease if its dihedral angle beats
+  /// the mean background of its two ends by at least `min_angle`, so curved
+  /// surfaces made of many gently bent edges are not picked up, while edges
+  /// that bend sharply relative to their surroundings are
+  pub fn select_creases(&mut self, min_angle: f64) -> FFIResult<()> {
+    if min_angle < 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let mut by_edge: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    for (i, t) in self.triangles.iter().enumerate() {
+      for j in 0..3 {
+        let (a, b) = (t[j], t[(j + 1) % 3]);
+        by_edge.entry((a.min(b), a.max(b))).or_default().push(i as u32);
+      }
+    }
+    
+    let dihedrals: Vec<((u32, u32), f64)> = by_edge.iter().filter(|(_, t)| {
+      t.len() == 2
+    }).map(|(&edge, t)| {
+      let normals = [t[0], t[1]].map(|i| self.face_normal(i as usize));
+      (edge, normals[0].angle(&normals[1]))
+    }).collect();
+    
+    let mut bending = vec![(0.0, 0.0); self.vertices.len()];
+    for &((a, b), angle) in &dihedrals {
+      if angle >= min_angle { continue };
+      
+      let length = (self.vertices[a as usize] - self.vertices[b as usize])
+        .norm();
+      for v in [a, b] {
+        bending[v as usize].0 += length*angle;
+        bending[v as usize].1 += length;
+      }
+    }
+    let background: Vec<f64> = bending.iter().map(|&(sum, length)| {
+      if length > 0.0 { sum/length } else { 0.0 }
+    }).collect();
+    
+    let mut selection: Vec<u32> = dihedrals.iter().filter(|&&((a, b), angle)| {
+      angle - (background[a as usize] + background[b as usize])/2.0 >=
+        min_angle
+    }).flat_map(|&((a, b), _)| [a, b]).collect();
+    selection.sort_unstable();
+    selection.dedup();
+    
+    self.selection = selection;
+    self.selection_type = SelectionType::VERTICES;
+    
+    return Ok(());
+  }
+  
+  /// Number of separate loops formed by edges used by only one triangle. Each
+  /// loop is walked edge to edge in winding order. Where loops touch at a
+  /// vertex, the walk may combine them, so such holes can count as one
+  pub fn hole_count(&self) -> usize {
+    let all: Vec<u32> = (0..self.triangles.len() as u32).collect();
+    let edges = self.boundary_edges(&all);
+    
+    let mut outgoing: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (i, &[a, _]) in edges.iter().enumerate() {
+      outgoing.entry(a).or_default().push(i);
+    }
+    
+    let mut used = vec![false; edges.len()];
+    let mut count = 0;
+    for start in 0..edges.len() {
+      if used[start] { continue };
+      count += 1;
+      
+      let mut current = start;
+      loop {
+        used[current] = true;
+        let end = edges[current][1];
+        let Some(&next) = outgoing.get(&end).into_iter().flatten().find(|&&i| {
+          !used[i]
+        }) else { break };
+        current = next;
+      }
+    }
+    
+    return count;
+  }
+  
+  /// Number of edges not shared by exactly two triangles. Zero for a closed
+  /// manifold mesh
+  pub fn nonmanifold_edge_count(&self) -> usize {
+    let all: Vec<u32> = (0..self.triangles.len() as u32).collect();
+    
+    return self.edge_uses(&all).values().filter(|&&uses| uses != 2).count();
+  }
+  
+  /// Groups edge-adjacent triangles whose normals are within
+  /// `angle_tolerance` radians of the group's first triangle, and refills each
+  /// group with a fan from one boundary vertex. Vertices left inside a group
+  /// are removed.
+  ///
+  /// Only groups whose outline is a single convex loop are rebuilt. Groups
+  /// with holes, concave outlines, or no fan origin that avoids sliver
+  /// triangles are left as they are. Clears the selection if anything changes
+  pub fn merge_coplanar(&mut self, angle_tolerance: f64) -> FFIResult<()> {
+    if angle_tolerance < 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let normals: Vec<Option<V3<f64>>> = (0..self.triangles.len()).map(|i| {
+      Some(self.face_normal(i)).filter(|normal| *normal != V3::zeros())
+    }).collect();
+    
+    let mut by_edge: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    for (i, t) in self.triangles.iter().enumerate() {
+      for j in 0..3 {
+        let (a, b) = (t[j], t[(j + 1) % 3]);
+        by_edge.entry((a.min(b), a.max(b))).or_default().push(i as u32);
+      }
+    }
+    
+    let min_dot = angle_tolerance.min(std::f64::consts::PI).cos();
+    let mut grouped = vec![false; self.triangles.len()];
+    let mut replaced = vec![false; self.triangles.len()];
+    let mut fans: Vec<[u32; 3]> = Vec::new();
+    
+    for seed in 0..self.triangles.len() {
+      if grouped[seed] { continue };
+      grouped[seed] = true;
+      let Some(normal) = normals[seed] else { continue };
+      
+      let mut group = vec![seed as u32];
+      let mut next = 0;
+      while next < group.len() {
+        let t = self.triangles[group[next] as usize];
+        next += 1;
+        
+        for j in 0..3 {
+          let (a, b) = (t[j], t[(j + 1) % 3]);
+          for &other in &by_edge[&(a.min(b), a.max(b))] {
+            let other_normal = normals[other as usize];
+            if !grouped[other as usize] && other_normal.is_some_and(|n| {
+              n.dot(&normal) >= min_dot
+            }) {
+              grouped[other as usize] = true;
+              group.push(other);
+            }
+          }
+        }
+      }
+      if group.len() < 2 { continue };
+      
+      let Some(outline) = self.outline(&group) else { continue };
+      if outline.len() >= group.len() + 2 { continue };
+      
+      let corner = |i: usize| {
+        let n = outline.len();
+        let [a, b, c] = [i + n - 1, i, i + 1].map(|k| {
+          self.vertices[outline[k % n] as usize]
+        });
+        (b - a).cross(&(c - b)).dot(&normal)
+      };
+      if (0..outline.len()).any(|i| corner(i) < -1e-9) { continue };
+      
+      let fan = |origin: usize| -> Option<Vec<[u32; 3]>> {
+        let n = outline.len();
+        (1..n - 1).map(|k| {
+          let t = [outline[origin], outline[(origin + k) % n],
+            outline[(origin + k + 1) % n]];
+          let [a, b, c] = t.map(|i| self.vertices[i as usize]);
+          let doubled_area = (b - a).cross(&(c - a)).norm();
+          let longest = (b - a).norm().max((c - b).norm()).max((a - c).norm());
+          (doubled_area > 1e-9*longest*longest).then_some(t)
+        }).collect()
+      };
+      let Some(triangles) = (0..outline.len()).find_map(fan) else { continue };
+      
+      for &i in &group {
+        replaced[i as usize] = true;
+      }
+      fans.extend(triangles);
+    }
+    
+    if fans.is_empty() { return Ok(()) };
+    
+    // Vertices only used by replaced triangles were inside a group
+    let mut keep = vec![true; self.vertices.len()];
+    for (i, triangle) in self.triangles.iter().enumerate() {
+      if replaced[i] {
+        for &index in triangle {
+          keep[index as usize] = false;
+        }
+      }
+    }
+    
+    let mut i = 0;
+    self.triangles.retain(|_| {
+      i += 1;
+      !replaced[i - 1]
+    });
+    self.triangles.extend(fans);
+    
+    for &index in self.triangles.iter().flatten() {
+      keep[index as usize] = true;
+    }
+    for &index in self.edges.iter().flatten() {
+      keep[index as usize] = true;
+    }
+    
+    self.compact_vertices(&keep);
+    
+    return Ok(());
+  }
+  
+  /// Whether triangles sharing an edge all traverse it in opposite
+  /// directions. A reversed triangle shows up as the same directed edge being
+  /// used twice
+  pub fn is_winding_consistent(&self) -> bool {
+    let mut directed: HashMap<(u32, u32), u32> = HashMap::new();
+    for triangle in &self.triangles {
+      for j in 0..3 {
+        let uses = directed.entry((triangle[j], triangle[(j + 1) % 3]))
+          .or_insert(0);
+        *uses += 1;
+        if *uses > 1 { return false };
+      }
+    }
+    
+    return true;
+  }
+  
+  /// Whether the mesh is watertight: it has triangles, every edge is shared by
+  /// exactly two of them, and they agree on winding
+  pub fn is_closed(&self) -> bool {
+    return !self.triangles.is_empty() && self.nonmanifold_edge_count() == 0 &&
+      self.is_winding_consistent();
+  }
+  
+  /// Signed volume enclosed by the triangles, positive if they face outward.
+  /// Only meaningful for closed meshes
+  pub fn volume(&self) -> f64 {
+    return self.triangles.iter().map(|triangle| {
+      let [a, b, c] = triangle.map(|i| self.vertices[i as usize]);
+      a.dot(&b.cross(&c))
+    }).sum::<f64>()/6.0;
+  }
+  
+  /// Center of mass of the enclosed solid, assuming uniform density. Sums the
+  /// signed tetrahedra from the origin to each triangle, as volume() does.
+  /// Only meaningful for closed meshes. Zero if no volume is enclosed
+  pub fn volume_centroid(&self) -> V3<f64> {
+    let (moment, volume) = self.triangles.iter().fold((V3::zeros(), 0.0),
+    |(moment, volume), triangle| {
+      let [a, b, c] = triangle.map(|i| self.vertices[i as usize]);
+      let tetrahedron = a.dot(&b.cross(&c))/6.0;
+      (moment + (a + b + c)/4.0*tetrahedron, volume + tetrahedron)
+    });
+    
+    if volume == 0.0 { return V3::zeros() };
+    return moment/volume;
+  }
+  
+  /// Flips each triangle whose normal points away from the normal of the
+  /// nearest triangle in `reference`, measured between centroids. Useful for
+  /// matching a rebuilt part to the orientation of the original. Every pair
+  /// of triangles is compared, so this is slow for large meshes
+  pub fn orient_like(&mut self, reference: &Geometry) -> FFIResult<()> {
+    if reference.triangles.is_empty() {
+      return Err(ErrorCode::EmptyGeometry);
+    }
+    
+    let centroid = |geometry: &Geometry, triangle: usize| {
+      geometry.triangles[triangle].iter().fold(V3::zeros(), |sum, &i| {
+        sum + geometry.vertices[i as usize]
+      })/3.0
+    };
+    let reference_centroids: Vec<V3<f64>> = (0..reference.triangles.len())
+      .map(|i| centroid(reference, i)).collect();
+    
+    for i in 0..self.triangles.len() {
+      let point = centroid(self, i);
+      
+      // Can .unwrap() because reference has triangles
+      let nearest = (0..reference_centroids.len()).min_by(|&a, &b| {
+        (reference_centroids[a] - point).norm_squared().total_cmp(
+          &(reference_centroids[b] - point).norm_squared())
+      }).unwrap();
+      
+      if self.face_normal(i).dot(&reference.face_normal(nearest)) < 0.0 {
+        self.triangles[i].swap(1, 2);
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Sweeps the selected triangles around an axis through `center`, making
+  /// `steps` rotated copies of their vertices and bridging the boundary edges
+  /// of consecutive copies with walls. The last copy is capped with the
+  /// selected triangles. Original triangles are kept, and one of the two caps
+  /// is flipped so the result faces outward. Selection becomes the last copy.
+  ///
+  /// If `angle` is a full turn (±2π), the last copy would land on the
+  /// original, so the walls join back onto the original vertices instead and
+  /// the selected triangles, which would be inside the solid, are removed.
+  /// This needs at least 3 steps, and selection becomes the original vertices
+  pub fn spin(&mut self, steps: u32, angle: f64, axis: V3<f64>,
+  center: V3<f64>) -> FFIResult<()> {
+    let full_turn = (angle.abs() - std::f64::consts::TAU).abs() < 1e-9;
+    if steps == 0 || (full_turn && steps < 3) {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    let axis = Unit::try_new(axis, 0.0).ok_or(
+      ErrorCode::ParameterOutOfRange)?;
+    self.check_vertex_limit((steps as u64).saturating_mul(
+      self.selected_vertices().len() as u64))?;
+    
+    self.sweep(steps, full_turn, |step, point| {
+      let rotation = Rotation3::from_axis_angle(&axis,
+        angle*(step as f64)/(steps as f64));
+      center + rotation*(point - center)
+    });
+    
+    return Ok(());
+  }
+  
+  /// Splits each selected triangle into four through its edge midpoints,
+  /// `iterations` times. Selected triangles sharing an edge share its
+  /// midpoint, so they stay connected. Unselected neighbors are split into
+  /// two or three triangles to meet those midpoints (four if every edge has
+  /// one), so no cracks open at the border. Selection becomes the new
+  /// triangles
+  pub fn subdivide(&mut self, iterations: u32) -> FFIResult<()> {
+    let mut triangles = self.selected_triangles();
+    
+    for _ in 0..iterations {
+      self.check_vertex_limit((triangles.len() as u64)*3)?;
+      
+      // Keyed by the edge's vertices, smaller index first
+      let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+      let mut ends = Vec::new();
+      let mut midpoint = |vertices: &mut Vec<V3<f64>>, a: u32, b: u32| {
+        *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+          vertices.push((vertices[a as usize] + vertices[b as usize])/2.0);
+          ends.push([a, b]);
+          (vertices.len() - 1) as u32
+        })
+      };
+      
+      let mut split = Vec::with_capacity(triangles.len()*4);
+      for &i in &triangles {
+        let [a, b, c] = self.triangles[i as usize];
+        let ab = midpoint(&mut self.vertices, a, b);
+        let bc = midpoint(&mut self.vertices, b, c);
+        let ca = midpoint(&mut self.vertices, c, a);
+        
+        self.triangles[i as usize] = [a, ab, ca];
+        split.push(i);
+        for triangle in [[ab, b, bc], [ca, bc, c], [ab, bc, ca]] {
+          split.push(self.triangles.len() as u32);
+          self.triangles.push(triangle);
+        }
+      }
+      
+      // Unselected triangles with a midpoint on an edge are split to use it.
+      // Each is turned so its first edge has a midpoint and, unless all three
+      // do, its last edge does not
+      let mut is_split = vec![false; self.triangles.len()];
+      for &i in &split { is_split[i as usize] = true };
+      for (i, &done) in is_split.iter().enumerate() {
+        if done { continue };
+        
+        let mut corners = self.triangles[i];
+        let mut mids = [0, 1, 2].map(|k| {
+          let (a, b) = (corners[k], corners[(k + 1)%3]);
+          midpoints.get(&(a.min(b), a.max(b))).copied()
+        });
+        let count = mids.iter().flatten().count();
+        if count == 0 { continue };
+        while mids[0].is_none() || (count < 3 && mids[2].is_some()) {
+          corners.rotate_left(1);
+          mids.rotate_left(1);
+        }
+        
+        let [a, b, c] = corners;
+        let pieces = match mids {
+          [Some(ab), None, None] => vec![[a, ab, c], [ab, b, c]],
+          [Some(ab), Some(bc), None] => {
+            vec![[a, ab, bc], [ab, b, bc], [a, bc, c]]
+          },
+          [Some(ab), Some(bc), Some(ca)] => {
+            vec![[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+          },
+          _ => unreachable!(),
+        };
+        self.triangles[i] = pieces[0];
+        self.triangles.extend_from_slice(&pieces[1..]);
+      }
+      
+      // Both halves of a creased edge keep its weight
+      let start = self.vertices.len() - ends.len();
+      for (k, &[a, b]) in ends.iter().enumerate() {
+        let Some(weight) = self.creases.remove(&(a.min(b), a.max(b))) else {
+          continue;
+        };
+        let middle = start as u32 + k as u32;
+        self.creases.insert((a.min(middle), a.max(middle)), weight);
+        self.creases.insert((b.min(middle), b.max(middle)), weight);
+      }
+      
+      // Midpoints take the average color of their edge
+      if let Some(colors) = self.colors.as_mut() {
+        colors.resize(start, OPAQUE_WHITE);
+        for [a, b] in ends {
+          let [a, b] = [a, b].map(|i| colors[i as usize]);
+          colors.push([0, 1, 2, 3].map(|k| (a[k] + b[k])/2.0));
+        }
+      }
+      
+      triangles = split;
+    }
+    self.clear_vertex_attributes();
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::TRIANGLES;
+    self.selection.extend(triangles);
+    
+    return Ok(());
+  }
+  
+  /// Moves each selected vertex along its area-weighted normal by
+  /// `amplitude` times value noise sampled at `frequency` times its position.
+  /// The same seed always gives the same result. Vertices not used by any
+  /// triangle have no normal and stay put
+  pub fn displace_noise(&mut self, amplitude: f64, frequency: f64, seed: u32) {
+    let mut normals = vec![V3::zeros(); self.vertices.len()];
+    for triangle in &self.triangles {
+      let corners = triangle.map(|i| self.vertices[i as usize]);
+      let cross = (corners[1] - corners[0]).cross(&(corners[2] - corners[0]));
+      for &i in triangle {
+        normals[i as usize] += cross;
+      }
+    }
+    
+    for i in self.selected_vertices() {
+      let Some(normal) = normals[i as usize].try_normalize(0.0) else {
+        continue;
+      };
+      let vertex = &mut self.vertices[i as usize];
+      *vertex += normal*amplitude*value_noise(*vertex*frequency, seed);
+    }
+  }
+  
+  /// Laplacian smoothing. Each iteration moves every selected vertex `factor`
+  /// of the way toward the average of its neighbors along triangle edges.
+  /// Unselected vertices stay pinned, and selected vertices with no selected
+  /// neighbor do not move.
+  ///
+  /// Creased edges (see set_crease()) stay sharp. A vertex on exactly two of
+  /// them is smoothed only along them, and one on three or more stays put.
+  /// Weights below 1 blend that with ordinary smoothing
+  pub fn smooth(&mut self, iterations: u32, factor: f64) -> FFIResult<()> {
+    if !(0.0..=1.0).contains(&factor) {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    
+    let selected = self.selected_vertices();
+    let mut is_selected = vec![false; self.vertices.len()];
+    for &i in &selected { is_selected[i as usize] = true };
+    
+    // Adjacency does not change between iterations, so is built once
+    let neighbors = self.vertex_neighbors();
+    let mut creased: Vec<Vec<(u32, f64)>> = vec![Vec::new(); neighbors.len()];
+    for (&(a, b), &weight) in &self.creases {
+      if !neighbors[a as usize].contains(&b) { continue };
+      creased[a as usize].push((b, weight));
+      creased[b as usize].push((a, weight));
+    }
+    
+    for _ in 0..iterations {
+      let moved: Vec<(u32, V3<f64>)> = selected.iter().filter(|&&v| {
+        neighbors[v as usize].iter().any(|&i| is_selected[i as usize])
+      }).map(|&v| {
+        let list = &neighbors[v as usize];
+        let position = self.vertices[v as usize];
+        let mut average = list.iter().fold(V3::zeros(), |sum, &i| {
+          sum + self.vertices[i as usize]
+        })/(list.len() as f64);
+        
+        let edges = &creased[v as usize];
+        if edges.len() >= 2 {
+          let sharp = edges.iter().fold(0.0f64, |max, &(_, w)| max.max(w))
+            .min(1.0);
+          let along = if edges.len() == 2 {
+            (self.vertices[edges[0].0 as usize] +
+              self.vertices[edges[1].0 as usize])/2.0
+          } else {
+            position
+          };
+          average = average*(1.0 - sharp) + along*sharp;
+        }
+        
+        (v, position + (average - position)*factor)
+      }).collect();
+      
+      for (v, position) in moved {
+        self.vertices[v as usize] = position;
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Laplacian smoothing of open edges only. Each boundary vertex with exactly
+  /// two boundary neighbors moves halfway toward their midpoint, once per
+  /// iteration. Interior vertices and boundary corners where loops touch are
+  /// left in place
+  pub fn smooth_boundary(&mut self, iterations: u32) {
+    let all: Vec<u32> = (0..self.triangles.len() as u32).collect();
+    
+    let mut neighbors: HashMap<u32, Vec<u32>> = HashMap::new();
+    for [a, b] in self.boundary_edges(&all) {
+      neighbors.entry(a).or_default().push(b);
+      neighbors.entry(b).or_default().push(a);
+    }
+    neighbors.retain(|_, list| list.len() == 2);
+    
+    for _ in 0..iterations {
+      let moved: Vec<(u32, V3<f64>)> = neighbors.iter().map(|(&v, list)| {
+        let midpoint = (self.vertices[list[0] as usize] +
+          self.vertices[list[1] as usize])/2.0;
+        (v, (self.vertices[v as usize] + midpoint)/2.0)
+      }).collect();
+      
+      for (v, position) in moved {
+        self.vertices[v as usize] = position;
+      }
+    }
+  }
+  
+  /// Turns a closed outline in the XY plane into a flat wall footprint facing
+  /// +Z, by offsetting the outline inward by `thickness` and filling the ring
+  /// between. The outline is the selected vertices, connected by the edges
+  /// between them if there are any, otherwise taken in index order. Errors if
+  /// those edges do not form one closed loop through every selected vertex.
+  /// Selection becomes the ring, ready to be extruded into a wall
+  pub fn thicken_profile(&mut self, thickness: f64) -> FFIResult<()> {
+    if thickness <= 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let selected = self.selected_vertices();
+    if selected.len() < 3 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let mut profile = selected.clone();
+    if self.edges.iter().any(|e| e.iter().all(|&v| self.is_selected(v))) {
+      let mut neighbors: HashMap<u32, Vec<u32>> = HashMap::new();
+      for &[a, b] in &self.edges {
+        if self.is_selected(a) && self.is_selected(b) {
+          neighbors.entry(a).or_default().push(b);
+          neighbors.entry(b).or_default().push(a);
+        }
+      }
+      if selected.iter().any(|v| neighbors.get(v).map(Vec::len) != Some(2)) {
+        return Err(ErrorCode::ParameterOutOfRange);
+      }
+      
+      profile = vec![selected[0]];
+      let mut previous = selected[0];
+      let mut current = neighbors[&selected[0]][0];
+      while current != selected[0] {
+        if profile.len() == selected.len() {
+          return Err(ErrorCode::ParameterOutOfRange);
+        }
+        profile.push(current);
+        
+        let list = &neighbors[&current];
+        let next = if list[0] == previous { list[1] } else { list[0] };
+        previous = current;
+        current = next;
+      }
+      if profile.len() != selected.len() {
+        return Err(ErrorCode::ParameterOutOfRange);
+      }
+    }
+    
+    let n = profile.len();
+    let doubled_area: f64 = (0..n).map(|i| {
+      let a = self.vertices[profile[i] as usize];
+      let b = self.vertices[profile[(i + 1) % n] as usize];
+      a.x*b.y - b.x*a.y
+    }).sum();
+    if doubled_area == 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    if doubled_area < 0.0 { profile.reverse() };
+    
+    let point = |i: usize| self.vertices[profile[i % n] as usize];
+    // With the outline counterclockwise, inward is to the left of each edge
+    let inward = |i: usize| {
+      let direction = (point(i + 1) - point(i)).xy();
+      V3::new(-direction.y, direction.x, 0.0).try_normalize(0.0)
+    };
+    let mut inner = Vec::with_capacity(n);
+    for i in 0..n {
+      let (Some(before), Some(after)) = (inward(i + n - 1), inward(i)) else {
+        return Err(ErrorCode::ParameterOutOfRange);
+      };
+      let denominator = 1.0 + before.dot(&after);
+      if denominator < 1e-6 { return Err(ErrorCode::ParameterOutOfRange) };
+      
+      inner.push(point(i) + (before + after)*thickness/denominator);
+    }
+    
+    let offset = self.vertices.len() as u32;
+    self.vertices.extend(inner);
+    self.copy_attributes(&profile);
+    self.clear_vertex_attributes();
+    
+    for i in 0..n {
+      let j = (i + 1) % n;
+      let (outer_i, outer_j) = (profile[i], profile[j]);
+      let (inner_i, inner_j) = (offset + i as u32, offset + j as u32);
+      
+      self.triangles.push([outer_i, outer_j, inner_j]);
+      self.triangles.push([outer_i, inner_j, inner_i]);
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(selected);
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Gives the selected surface thickness: makes a copy offset by `thickness`
+  /// against the surface's vertex normals, facing the other way, and joins
+  /// the two along the surface's boundary, so an open surface becomes a
+  /// closed solid. Selection becomes the original and the copy
+  pub fn solidify(&mut self, thickness: f64) -> FFIResult<()> {
+    if thickness <= 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let triangles = self.selected_triangles();
+    if triangles.is_empty() { return Err(ErrorCode::EmptySelection) };
+    
+    let mut used: Vec<u32> = triangles.iter().flat_map(|&i| {
+      self.triangles[i as usize]
+    }).collect();
+    used.sort_unstable();
+    used.dedup();
+    self.check_vertex_limit(used.len() as u64)?;
+    
+    // Length of the cross product is twice the triangle's area, so these are
+    // area-weighted
+    let mut normals: HashMap<u32, V3<f64>> = HashMap::new();
+    for &i in &triangles {
+      let triangle = self.triangles[i as usize];
+      let corners = triangle.map(|j| self.vertices[j as usize]);
+      let cross = (corners[1] - corners[0]).cross(&(corners[2] - corners[0]));
+      for j in triangle {
+        *normals.entry(j).or_insert(V3::zeros()) += cross;
+      }
+    }
+    
+    let base = self.vertices.len() as u32;
+    let copy: HashMap<u32, u32> = used.iter().enumerate().map(|(i, &v)| {
+      (v, base + i as u32)
+    }).collect();
+    for &v in &used {
+      let normal = normals[&v].try_normalize(0.0).unwrap_or(V3::zeros());
+      self.vertices.push(self.vertices[v as usize] - normal*thickness);
+    }
+    self.copy_attributes(&used);
+    self.clear_vertex_attributes();
+    
+    for [a, b] in self.boundary_edges(&triangles) {
+      self.triangles.push([b, a, copy[&a]]);
+      self.triangles.push([b, copy[&a], copy[&b]]);
+    }
+    for &i in &triangles {
+      let [a, b, c] = self.triangles[i as usize];
+      self.triangles.push([copy[&c], copy[&b], copy[&a]]);
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(&used);
+    self.selection.extend(base..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Copies the selected triangles once, moved by `displacement`, and joins
+  /// the copy to the original with side walls. Unlike a plain move, the
+  /// original faces are always kept (flipped to face outward), so this works
+  /// the same whether or not the whole mesh is selected. Selection becomes the
+  /// new cap
+  pub fn extrude_copy(&mut self, displacement: V3<f64>) {
+    self.sweep(1, false, |_, point| point + displacement);
+  }
+  
+  /// Sweeps the selected profile along a polyline. The profile is taken to sit
+  /// at `path[0]`, and is carried to each later point with a rotation that
+  /// follows the path tangent (parallel transport, so it does not twist). If
+  /// triangles are selected they form a closed profile and are capped at both
+  /// ends as in extrude_copy(). Otherwise the selected vertices, in selection
+  /// order, form an open profile and only walls are made. Selection becomes
+  /// the last copy
+  pub fn extrude_path(&mut self, path: &[V3<f64>]) -> FFIResult<()> {
+    if path.len() < 2 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let segments: Vec<V3<f64>> = path.windows(2).map(|pair| {
+      (pair[1] - pair[0]).try_normalize(0.0)
+    }).collect::<Option<_>>().ok_or(ErrorCode::ParameterOutOfRange)?;
+    
+    // Tangent at each point averages the segments meeting there
+    let tangents: Vec<V3<f64>> = (0..path.len()).map(|i| {
+      let before = segments[i.saturating_sub(1)];
+      let after = segments[i.min(segments.len() - 1)];
+      (before + after).try_normalize(0.0).unwrap_or(after)
+    }).collect();
+    
+    let mut rotations = vec![Rotation3::identity()];
+    for pair in tangents.windows(2) {
+      let turn = Rotation3::rotation_between(&pair[0], &pair[1])
+        .unwrap_or_else(|| {
+          // Tangent reversed, so any perpendicular axis will do
+          let axis = pair[0].cross(&V3::x()).try_normalize(1e-6)
+            .unwrap_or_else(|| pair[0].cross(&V3::y()).normalize());
+          Rotation3::from_axis_angle(&Unit::new_unchecked(axis),
+            std::f64::consts::PI)
+        });
+      rotations.push(turn*rotations[rotations.len() - 1]);
+    }
+    
+    let steps = (path.len() - 1) as u32;
+    let place = |step: u32, point: V3<f64>| {
+      path[step as usize] + rotations[step as usize]*(point - path[0])
+    };
+    
+    if !self.selected_triangles().is_empty() {
+      self.check_vertex_limit((steps as u64).saturating_mul(
+        self.selected_vertices().len() as u64))?;
+      self.sweep(steps, false, place);
+      return Ok(());
+    }
+    
+    let profile = match self.selection_type {
+      SelectionType::VERTICES => self.selection.clone(),
+      SelectionType::TRIANGLES => Vec::new(),
+    };
+    if profile.len() < 2 { return Ok(()) };
+    self.check_vertex_limit((steps as u64).saturating_mul(
+      profile.len() as u64))?;
+    
+    let base = self.vertices.len() as u32;
+    let count = profile.len() as u32;
+    for step in 1..=steps {
+      for &v in &profile {
+        self.vertices.push(place(step, self.vertices[v as usize]));
+      }
+    }
+    self.copy_attributes(&profile.repeat(steps as usize));
+    self.clear_vertex_attributes();
+    
+    let copy = |step: u32, i: u32| -> u32 {
+      if step == 0 { profile[i as usize] } else {
+        base + (step - 1)*count + i
+      }
+    };
+    for step in 0..steps {
+      for i in 0..count - 1 {
+        let (a0, b0) = (copy(step, i), copy(step, i + 1));
+        let (a1, b1) = (copy(step + 1, i), copy(step + 1, i + 1));
+        self.triangles.push([a0, b0, b1]);
+        self.triangles.push([a0, b1, a1]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection.extend(base + (steps - 1)*count..base + steps*count);
+    
+    return Ok(());
+  }
+  
+  /// Shared body of spin() and extrude_copy(). Copy `step` of each vertex used
+  /// by a selected triangle is placed at `place(step, original)`. If
+  /// `closed` is set, step `steps` is taken to land back on the original, so
+  /// the walls end on the original vertices and the selected triangles are
+  /// removed instead of capping both ends
+  fn sweep(&mut self, steps: u32, closed: bool,
+  place: impl Fn(u32, V3<f64>) -> V3<f64>) {
+    let triangles = self.selected_triangles();
+    if triangles.is_empty() { return };
+    
+    let mut used: Vec<u32> = triangles.iter().flat_map(|&i| {
+      self.triangles[i as usize]
+    }).collect();
+    used.sort_unstable();
+    used.dedup();
+    let position: HashMap<u32, u32> = used.iter().enumerate().map(|(i, &v)| {
+      (v, i as u32)
+    }).collect();
+    
+    let base = self.vertices.len() as u32;
+    let count = used.len() as u32;
+    let copies = if closed { steps - 1 } else { steps };
+    let copy = |step: u32, vertex: u32| -> u32 {
+      if step == 0 || step > copies { vertex } else {
+        base + (step - 1)*count + position[&vertex]
+      }
+    };
+    
+    for step in 1..=copies {
+      for &v in &used {
+        self.vertices.push(place(step, self.vertices[v as usize]));
+      }
+    }
+    self.copy_attributes(&used.repeat(copies as usize));
+    self.clear_vertex_attributes();
+    
+    // If the selected triangles move along their own normals on the first
+    // step, they face into the solid and are flipped to make the first cap.
+    // Weighting each triangle's motion by its area gives the sign of the
+    // volume swept out by that step
+    let forward = triangles.iter().map(|&i| {
+      let corners = self.triangles[i as usize];
+      let [a, b, c] = corners.map(|j| self.vertices[j as usize]);
+      let motion = corners.iter().fold(V3::zeros(), |sum, &j| {
+        sum + self.vertices[copy(1, j) as usize] - self.vertices[j as usize]
+      });
+      motion.dot(&(b - a).cross(&(c - a)))
+    }).sum::<f64>() > 0.0;
+    
+    for [a, b] in self.boundary_edges(&triangles) {
+      for step in 0..steps {
+        let (a0, b0) = (copy(step, a), copy(step, b));
+        let (a1, b1) = (copy(step + 1, a), copy(step + 1, b));
+        
+        if forward {
+          self.triangles.push([a0, b0, b1]);
+          self.triangles.push([a0, b1, a1]);
+        } else {
+          self.triangles.push([a0, b1, b0]);
+          self.triangles.push([a0, a1, b1]);
+        }
+      }
+    }
+    
+    if closed {
+      let mut keep = vec![true; self.triangles.len()];
+      for &i in &triangles { keep[i as usize] = false };
+      let mut keep = keep.into_iter();
+      self.triangles.retain(|_| keep.next().unwrap());
+      
+      self.selection.drain(..);
+      self.selection_type = SelectionType::VERTICES;
+      self.selection.extend(used);
+      return;
+    }
+    
+    for &i in &triangles {
+      let [t0, t1, t2] = self.triangles[i as usize];
+      
+      if forward {
+        self.triangles[i as usize] = [t0, t2, t1];
+        self.triangles.push([copy(steps, t0), copy(steps, t1),
+          copy(steps, t2)]);
+      } else {
+        self.triangles.push([copy(steps, t0), copy(steps, t2),
+          copy(steps, t1)]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(base + (steps - 1)*count..base + steps*count);
+  }
+  
+  /// Points every vertex normal in the same direction, for flat stylized
+  /// shading such as foliage cards
+  pub fn set_normals_direction(&mut self, direction: V3<f64>) -> FFIResult<()> {
+    let direction = direction.try_normalize(0.0).ok_or(
+      ErrorCode::ParameterOutOfRange)?;
+    
+    self.normals = Some(vec![direction; self.vertices.len()]);
+    
+    return Ok(());
+  }
+  
+  /// Free-form deformation of selected vertices by a trilinear lattice
+  /// spanning their bounding box. The lattice has `divisions` cells along
+  /// each axis, and `control_points` gives the deformed position of each
+  /// lattice node, with x varying fastest and z slowest. Control points at
+  /// their undeformed grid positions leave the geometry unchanged
+  pub fn lattice_deform(&mut self, divisions: [u32; 3],
+  control_points: &[V3<f64>]) -> FFIResult<()> {
+    if divisions.contains(&0) { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let [nx, ny, nz] = divisions.map(|d| d as usize + 1);
+    if control_points.len() != nx*ny*nz {
+      return Err(ErrorCode::ParameterCount);
+    }
+    
+    let selected = self.selected_vertices();
+    let Some(&first) = selected.first() else { return Ok(()) };
+    let (min, max) = selected.iter().fold((self.vertices[first as usize],
+      self.vertices[first as usize]), |(min, max), &i| {
+      (min.inf(&self.vertices[i as usize]), max.sup(&self.vertices[i as usize]))
+    });
+    
+    let node = |i: usize, j: usize, k: usize| control_points[i + nx*(j + ny*k)];
+    
+    for i in selected {
+      let vertex = &mut self.vertices[i as usize];
+      let mut cell = [0; 3];
+      let mut fraction = V3::zeros();
+      
+      for axis in 0..3 {
+        let extent = max[axis] - min[axis];
+        let position = if extent > 0.0 {
+          (vertex[axis] - min[axis])/extent*divisions[axis] as f64
+        } else { 0.0 };
+        
+        cell[axis] = (position.floor() as usize).min(divisions[axis] as usize
+          - 1);
+        fraction[axis] = position - cell[axis] as f64;
+      }
+      
+      let [x, y, z] = cell;
+      let [fx, fy, fz] = [fraction.x, fraction.y, fraction.z];
+      let lerp = |a: V3<f64>, b: V3<f64>, t: f64| a*(1.0 - t) + b*t;
+      
+      *vertex = lerp(
+        lerp(
+          lerp(node(x, y    , z    ), node(x + 1, y    , z    ), fx),
+          lerp(node(x, y + 1, z    ), node(x + 1, y + 1, z    ), fx),
+          fy,
+        ),
+        lerp(
+          lerp(node(x, y    , z + 1), node(x + 1, y    , z + 1), fx),
+          lerp(node(x, y + 1, z + 1), node(x + 1, y + 1, z + 1), fx),
+          fy,
+        ),
+        fz,
+      );
+    }
+    
+    return Ok(());
+  }
+  
+  /// Projects selected vertices through a pinhole camera into UV space, with
+  /// the image covering 0 to 1 on both axes. `fov` is the full field of view
+  /// in radians, and the camera's up is as close to +Z as possible. Vertices
+  /// behind the camera are clamped to the edge of the image in the direction
+  /// they lie, instead of being mirrored through the center
+  pub fn uv_project_camera(&mut self, position: V3<f64>, direction: V3<f64>,
+  fov: f64) -> FFIResult<()> {
+    if !(fov > 0.0 && fov < std::f64::consts::PI) {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    let forward = direction.try_normalize(0.0).ok_or(
+      ErrorCode::ParameterOutOfRange)?;
+    
+    let world_up = if forward.z.abs() < 0.999 { V3::z() } else { V3::y() };
+    let right = forward.cross(&world_up).normalize();
+    let up = right.cross(&forward);
+    let scale = 1.0/(fov/2.0).tan();
+    
+    let mut uvs = self.uvs.take().filter(|uvs| {
+      uvs.len() == self.vertices.len()
+    }).unwrap_or(vec![[0.0, 0.0]; self.vertices.len()]);
+    
+    for i in self.selected_vertices() {
+      let offset = self.vertices[i as usize] - position;
+      let (x, y, depth) = (offset.dot(&right), offset.dot(&up),
+        offset.dot(&forward));
+      
+      let (u, v) = if depth > 0.0 {
+        (x*scale/depth, y*scale/depth)
+      } else {
+        let edge = x.abs().max(y.abs());
+        if edge > 0.0 { (x/edge, y/edge) } else { (0.0, 0.0) }
+      };
+      
+      // GLTF puts the UV origin at the top left
+      uvs[i as usize] = [0.5 + 0.5*u, 0.5 - 0.5*v];
+    }
+    
+    self.uvs = Some(uvs);
+    
+    return Ok(());
+  }
+  
+  /// Sets UVs for all vertices by dropping coordinate `axis` (0 projects
+  /// onto YZ, 1 onto XZ, 2 onto XY) and stretching the projected bounding box
+  /// to cover 0 to 1. U follows the lower remaining axis and V the higher
+  pub fn generate_uv_planar(&mut self, axis: u8) -> FFIResult<()> {
+    if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let (a, b) = match axis { 0 => (1, 2), 1 => (0, 2), _ => (0, 1) };
+    let Some((min, max)) = self.aabb() else {
+      self.uvs = Some(Vec::new());
+      return Ok(());
+    };
+    let size = [max[a] - min[a], max[b] - min[b]];
+    let scale = size.map(|size| if size > 0.0 { 1.0/size } else { 0.0 });
+    
+    // GLTF puts the UV origin at the top left
+    self.uvs = Some(self.vertices.iter().map(|vertex| [
+      (vertex[a] - min[a])*scale[0],
+      1.0 - (vertex[b] - min[b])*scale[1],
+    ]).collect());
+    
+    return Ok(());
+  }
+  
+  /// Box unwrap: each triangle is projected along the axis its normal points
+  /// most toward, as seen from outside, into one of six square cells 1/3
+  /// wide. The top row holds +X, -X, +Y and the one below it -Y, +Z, -Z.
+  /// Every cell spans the bounding box of the whole geometry. Vertices shared
+  /// between directions are split, copying their normals
+  pub fn generate_uv_box(&mut self) {
+    let Some((min, max)) = self.aabb() else {
+      self.uvs = Some(Vec::new());
+      return;
+    };
+    let size = max - min;
+    let count = self.vertices.len();
+    
+    let mut normals = self.normals.take().filter(|normals| {
+      normals.len() == count
+    });
+    let mut uvs = vec![[0.0, 0.0]; count];
+    let mut owner: Vec<Option<usize>> = vec![None; count];
+    let mut copies: HashMap<(u32, usize), u32> = HashMap::new();
+    let mut sources = Vec::new();
+    
+    for i in 0..self.triangles.len() {
+      let normal = self.face_normal(i);
+      let axis = normal.iamax();
+      let side = axis*2 + (normal[axis] < 0.0) as usize;
+      
+      for j in 0..3 {
+        let vertex = self.triangles[i][j];
+        match owner[vertex as usize] {
+          None => owner[vertex as usize] = Some(side),
+          Some(first) if first == side => {},
+          Some(_) => {
+            self.triangles[i][j] = *copies.entry((vertex, side))
+            .or_insert_with(|| {
+              self.vertices.push(self.vertices[vertex as usize]);
+              if let Some(normals) = normals.as_mut() {
+                normals.push(normals[vertex as usize]);
+              }
+              uvs.push([0.0, 0.0]);
+              sources.push(vertex);
+              (self.vertices.len() - 1) as u32
+            });
+          },
+        }
+        
+        let (a, b) = match axis { 0 => (1, 2), 1 => (0, 2), _ => (0, 1) };
+        let position = self.vertices[self.triangles[i][j] as usize] - min;
+        let [mut u, v] = [(a, position[a]), (b, position[b])].map(
+          |(k, x)| if size[k] > 0.0 { x/size[k] } else { 0.0 });
+        
+        // Mirror sides whose in-plane axes are reversed when seen from outside
+        if matches!(side, 1 | 2 | 5) { u = 1.0 - u };
+        
+        // GLTF puts the UV origin at the top left
+        let (column, row) = ((side % 3) as f64, (side / 3) as f64);
+        uvs[self.triangles[i][j] as usize] = [(column + u)/3.0,
+          (row + 1.0 - v)/3.0];
+      }
+    }
+    
+    self.normals = normals;
+    self.uvs = Some(uvs);
+    self.copy_attributes(&sources);
+  }
+  
+  /// Bridges each closed vertex loop to the next with a band of triangles,
+  /// making one continuous surface through all of them. Loops must be the
+  /// same length, and vertex j of each loop is connected to vertex j of the
+  /// next. Triangles face outward when the loops run counterclockwise as seen
+  /// looking back from the last loop toward the first
+  pub fn loft(&mut self, loops: &[Vec<u32>]) -> FFIResult<()> {
+    if loops.len() < 2 { return Err(ErrorCode::ParameterCount) };
+    
+    let length = loops[0].len();
+    if length < 3 || loops.iter().any(|l| l.len() != length) {
+      return Err(ErrorCode::ParameterCount);
+    }
+    if loops.iter().flatten().any(|&v| v as usize >= self.vertices.len()) {
+      return Err(ErrorCode::VtxOutOfBounds);
+    }
+    
+    for pair in loops.windows(2) {
+      for j in 0..length {
+        let next = (j + 1) % length;
+        
+        let (a, b) = (pair[0][j], pair[0][next]);
+        let (c, d) = (pair[1][j], pair[1][next]);
+        
+        self.triangles.push([a, b, c]);
+        self.triangles.push([b, d, c]);
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Flips the edge shared by two triangles, so the quad they form is split
+  /// along its other diagonal. Returns false (and changes nothing) if the
+  /// triangles do not share an edge with opposite winding
+  pub fn flip_edge(&mut self, first: u32, second: u32) -> bool {
+    let t1 = self.triangles[first as usize];
+    let t2 = self.triangles[second as usize];
+    
+    for j in 0..3 {
+      let (a, b, c) = (t1[j], t1[(j + 1) % 3], t1[(j + 2) % 3]);
+      
+      for k in 0..3 {
+        if t2[k] == b && t2[(k + 1) % 3] == a {
+          let d = t2[(k + 2) % 3];
+          
+          self.triangles[first as usize] = [c, a, d];
+          self.triangles[second as usize] = [d, b, c];
+          return true;
+        }
+      }
+    }
+    
+    return false;
+  }
+  
+  /// For each pair of selected triangles forming a flat convex quad, moves the
+  /// shared edge to the quad's shorter diagonal. Quads that are bent by more
+  /// than about 2.5 degrees are left alone, since flipping them would change
+  /// the surface. Each triangle is flipped at most once per call
+  pub fn retriangulate_shortest_diagonal(&mut self) {
+    let selected = self.selected_triangles();
+    let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+    for &i in &selected {
+      let triangle = self.triangles[i as usize];
+      for j in 0..3 {
+        edges.insert((triangle[j], triangle[(j + 1) % 3]), i);
+      }
+    }
+    
+    let normal = |t: [u32; 3], vertices: &[V3<f64>]| {
+      let [a, b, c] = t.map(|i| vertices[i as usize]);
+      (b - a).cross(&(c - a))
+    };
+    
+    let mut flipped = vec![false; self.triangles.len()];
+    for &first in &selected {
+      for j in 0..3 {
+        if flipped[first as usize] { break };
+        
+        let t1 = self.triangles[first as usize];
+        let (a, b, c) = (t1[j], t1[(j + 1) % 3], t1[(j + 2) % 3]);
+        let Some(&second) = edges.get(&(b, a)) else { continue };
+        if second == first || flipped[second as usize] { continue };
+        
+        let t2 = self.triangles[second as usize];
+        let Some(d) = t2.iter().copied().find(|&v| v != a && v != b) else {
+          continue
+        };
+        
+        let v = |i: u32| self.vertices[i as usize];
+        if (v(c) - v(d)).norm() >= (v(a) - v(b)).norm() { continue };
+        
+        let n1 = self.face_normal(first as usize);
+        let n2 = self.face_normal(second as usize);
+        if n1.dot(&n2) < 0.999 { continue };
+        
+        // Both new triangles must still face the same way, or the quad was
+        // not convex
+        let n3 = normal([c, a, d], &self.vertices);
+        let n4 = normal([d, b, c], &self.vertices);
+        if n3.dot(&n1) <= 0.0 || n4.dot(&n1) <= 0.0 { continue };
+        
+        self.flip_edge(first, second);
+        flipped[first as usize] = true;
+        flipped[second as usize] = true;
+      }
+    }
+  }
+  
+  // rotations / matrices
+  
+  // Merges
+  
+  /// Removes every vertex whose entry in `keep` is false, shifting the
+  /// remaining vertices down and rewriting triangle indices to match.
+  /// Triangles that used a removed vertex are removed too. Clears selection
+  fn compact_vertices(&mut self, keep: &[bool]) {
+    let mut remap = vec![u32::MAX; self.vertices.len()];
+    let mut next = 0;
+    if let Some(colors) = self.colors.as_mut() {
+      colors.resize(self.vertices.len(), OPAQUE_WHITE);
+    }
+    
+    for i in 0..self.vertices.len() {
+      if keep[i] {
+        remap[i] = next;
+        self.vertices[next as usize] = self.vertices[i];
+        if let Some(colors) = self.colors.as_mut() {
+          colors[next as usize] = colors[i];
+        }
+        next += 1;
+      }
+    }
+    self.vertices.truncate(next as usize);
+    if let Some(colors) = self.colors.as_mut() {
+      colors.truncate(next as usize);
+    }
+    self.clear_vertex_attributes();
+    
+    self.triangles.retain_mut(|triangle| {
+      for index in triangle.iter_mut() {
+        *index = remap[*index as usize];
+      }
+      
+      !triangle.contains(&u32::MAX)
+    });
+    
+    self.edges.retain_mut(|edge| {
+      for index in edge.iter_mut() {
+        *index = remap[*index as usize];
+      }
+      
+      !edge.contains(&u32::MAX)
+    });
+    
+    self.creases = self.creases.drain().filter_map(|((a, b), weight)| {
+      let (a, b) = (remap[a as usize], remap[b as usize]);
+      if a == u32::MAX || b == u32::MAX || a == b { return None };
+      Some(((a.min(b), a.max(b)), weight))
+    }).collect();
+    
+    self.selection.drain(..);
+  }
+  
+  /// Snaps selected vertices to a point and welds them into a single vertex.
+  /// Triangles that collapse as a result are deleted. Selection becomes the
+  /// welded vertex
+  pub fn merge(&mut self, point: V3<f64>) {
+    let selected = self.selected_vertices();
+    let Some(&target) = selected.iter().min() else { return };
+    
+    self.vertices[target as usize] = point;
+    
+    let mut keep = vec![true; self.vertices.len()];
+    for &i in &selected {
+      keep[i as usize] = false;
+    }
+    keep[target as usize] = true;
+    
+    for triangle in &mut self.triangles {
+      for index in triangle.iter_mut() {
+        if !keep[*index as usize] {
+          *index = target;
+        }
+      }
+    }
+    self.triangles.retain(|triangle| {
+      triangle[0] != triangle[1] &&
+      triangle[1] != triangle[2] &&
+      triangle[2] != triangle[0]
+    });
+    
+    for edge in &mut self.edges {
+      for index in edge.iter_mut() {
+        if !keep[*index as usize] {
+          *index = target;
+        }
+      }
+    }
+    self.edges.retain(|edge| edge[0] != edge[1]);
+    
+    // All removed vertices have higher indices than target, so its index is
+    // unaffected by compacting
+    self.compact_vertices(&keep);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.push(target);
+  }
+  
+  /// Merges selected vertices at their centroid. Does nothing if fewer than
+  /// two vertices are selected
+  pub fn merge_center(&mut self) {
+    let selected = self.selected_vertices();
+    if selected.len() < 2 { return };
+    
+    let sum = selected.iter().fold(V3::zeros(), |sum, &i| {
+      sum + self.vertices[i as usize]
+    });
+    
+    self.merge(sum/(selected.len() as f64));
+  }
+  
+  /// Alias of merge_center(), under the name other modelling tools use. Welds
+  /// selected vertices into one at their average position, removing faces
+  /// that collapse
+  pub fn merge_average(&mut self) {
+    self.merge_center();
+  }
+  
+  /// Collapses triangle edges shorter than `min_length`, shortest first,
+  /// moving the two vertices to their midpoint and welding them. Triangles
+  /// that collapse are deleted. Edge lengths are checked again after each
+  /// collapse, since moving a vertex changes the edges around it. Returns the
+  /// number of edges collapsed
+  pub fn collapse_short_edges(&mut self, min_length: f64) -> usize {
+    let mut alive = vec![true; self.triangles.len()];
+    let mut around: Vec<Vec<usize>> = vec![Vec::new(); self.vertices.len()];
+    for (i, triangle) in self.triangles.iter().enumerate() {
+      for &v in triangle {
+        around[v as usize].push(i);
+      }
+    }
+    
+    // Lengths are never negative, so their bit patterns sort the same way as
+    // their values
+    let mut queue = std::collections::BinaryHeap::new();
+    let length = |vertices: &[V3<f64>], a: u32, b: u32| {
+      (vertices[a as usize] - vertices[b as usize]).norm()
+    };
+    for triangle in &self.triangles {
+      for j in 0..3 {
+        let (a, b) = (triangle[j], triangle[(j + 1) % 3]);
+        let l = length(&self.vertices, a, b);
+        if l < min_length {
+          queue.push(std::cmp::Reverse((l.to_bits(), a.min(b), a.max(b))));
+        }
+      }
+    }
+    
+    let mut keep = vec![true; self.vertices.len()];
+    let mut collapsed = 0;
+    while let Some(std::cmp::Reverse((bits, a, b))) = queue.pop() {
+      if !keep[a as usize] || !keep[b as usize] { continue };
+      if length(&self.vertices, a, b).to_bits() != bits { continue };
+      if !around[a as usize].iter().any(|&t| {
+        alive[t] && self.triangles[t].contains(&b)
+      }) { continue };
+      
+      self.vertices[a as usize] = (self.vertices[a as usize] +
+        self.vertices[b as usize])/2.0;
+      keep[b as usize] = false;
+      collapsed += 1;
+      
+      for t in std::mem::take(&mut around[b as usize]) {
+        if !alive[t] { continue };
+        
+        let triangle = &mut self.triangles[t];
+        if triangle.contains(&a) {
+          alive[t] = false;
+        } else {
+          for index in triangle.iter_mut().filter(|index| **index == b) {
+            *index = a;
+          }
+          around[a as usize].push(t);
+        }
+      }
+      
+      for &t in &around[a as usize] {
+        if !alive[t] { continue };
+        for &other in &self.triangles[t] {
+          let l = length(&self.vertices, a, other);
+          if other != a && l < min_length {
+            queue.push(std::cmp::Reverse((l.to_bits(), a.min(other),
+              a.max(other))));
+          }
+        }
+      }
+      
+      for index in self.edges.iter_mut().flatten() {
+        if *index == b { *index = a };
+      }
+    }
+    
+    if collapsed == 0 { return 0 };
+    
+    let mut i = 0;
+    self.triangles.retain(|_| {
+      i += 1;
+      alive[i - 1]
+    });
+    self.edges.retain(|edge| edge[0] != edge[1]);
+    self.compact_vertices(&keep);
+    
+    return collapsed;
+  }
+  
+  /// Welds every group of vertices closer than `epsilon` into the group's
+  /// lowest-index vertex, and drops triangles and edges that collapse.
+  /// Returns the number of vertices removed
+  pub fn remove_doubles(&mut self, epsilon: f64) -> FFIResult<usize> {
+    return self.remove_doubles_where(epsilon, |_, _| true);
+  }
+  
+  /// Alias of remove_doubles(), under the name other modelling tools use
+  pub fn weld(&mut self, epsilon: f64) -> FFIResult<usize> {
+    return self.remove_doubles(epsilon);
+  }
+  
+  /// Same as remove_doubles(), but only welds vertices whose face normals
+  /// (the average of the normals of the triangles using each vertex) are
+  /// within `max_angle` of each other. Vertices along a crease stay separate,
+  /// so a cube with split faces keeps its hard edges. Vertices not used by any
+  /// triangle weld freely
+  pub fn remove_doubles_respect_normals(&mut self, epsilon: f64,
+  max_angle: f64) -> FFIResult<usize> {
+    if max_angle < 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let mut sums = vec![V3::zeros(); self.vertices.len()];
+    for i in 0..self.triangles.len() {
+      let normal = self.face_normal(i);
+      for j in self.triangles[i] {
+        sums[j as usize] += normal;
+      }
+    }
+    let normals: Vec<Option<V3<f64>>> = sums.iter().map(|sum| {
+      sum.try_normalize(0.0)
+    }).collect();
+    
+    return self.remove_doubles_where(epsilon, |i, j| {
+      match (normals[i], normals[j]) {
+        (Some(a), Some(b)) => a.angle(&b) <= max_angle,
+        _ => true,
+      }
+    });
+  }
+  
+  /// Closes seams between open boundaries. Each open edge is paired with an
+  /// open edge running the opposite way whose ends are within `threshold` of
+  /// its own, and the paired ends are welded, so the result keeps a
+  /// consistent winding. Edges with no such partner, including edges of loops
+  /// that only partly line up, are left open. Clears vertex attributes and
+  /// selection
+  pub fn stitch_boundaries(&mut self, threshold: f64) -> FFIResult<()> {
+    if threshold <= 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let all: Vec<u32> = (0..self.triangles.len() as u32).collect();
+    let open = self.boundary_edges(&all);
+    if open.is_empty() { return Ok(()) };
+    
+    let midpoints: Vec<V3<f64>> = open.iter().map(|&[a, b]| {
+      (self.vertices[a as usize] + self.vertices[b as usize])/2.0
+    }).collect();
+    let grid = SpatialGrid::new(&midpoints, threshold);
+    let distance = |a: u32, b: u32| {
+      (self.vertices[a as usize] - self.vertices[b as usize]).norm()
+    };
+    
+    let mut target: Vec<u32> = (0..self.vertices.len() as u32).collect();
+    let find = |target: &mut Vec<u32>, mut i: u32| {
+      while target[i as usize] != i {
+        target[i as usize] = target[target[i as usize] as usize];
+        i = target[i as usize];
+      }
+      i
+    };
+    
+    let mut matched = vec![false; open.len()];
+    for i in 0..open.len() {
+      if matched[i] { continue };
+      let [a, b] = open[i];
+      
+      let partner = grid.nearby(&midpoints[i]).filter(|&j| {
+        let [c, d] = open[j as usize];
+        j as usize != i && !matched[j as usize] &&
+          distance(a, d) <= threshold && distance(b, c) <= threshold
+      }).min_by(|&j, &k| {
+        let [c, d] = open[j as usize];
+        let [e, f] = open[k as usize];
+        (distance(a, d) + distance(b, c)).total_cmp(
+          &(distance(a, f) + distance(b, e)))
+      });
+      let Some(j) = partner else { continue };
+      
+      matched[i] = true;
+      matched[j as usize] = true;
+      let [c, d] = open[j as usize];
+      for (x, y) in [(a, d), (b, c)] {
+        let (x, y) = (find(&mut target, x), find(&mut target, y));
+        target[x.max(y) as usize] = x.min(y);
+      }
+    }
+    
+    for i in 0..target.len() as u32 {
+      target[i as usize] = find(&mut target, i);
+    }
+    
+    for index in self.triangles.iter_mut().flatten() {
+      *index = target[*index as usize];
+    }
+    self.triangles.retain(|triangle| {
+      triangle[0] != triangle[1] &&
+      triangle[1] != triangle[2] &&
+      triangle[2] != triangle[0]
+    });
+    
+    for index in self.edges.iter_mut().flatten() {
+      *index = target[*index as usize];
+    }
+    self.edges.retain(|edge| edge[0] != edge[1]);
+    
+    let keep: Vec<bool> = target.iter().enumerate().map(|(i, &j)| {
+      i as u32 == j
+    }).collect();
+    self.compact_vertices(&keep);
+    
+    return Ok(());
+  }
+  
+  /// Shared body of the remove_doubles() variants. Vertex i only welds into j
+  /// if `can_weld(i, j)`
+  fn remove_doubles_where(&mut self, epsilon: f64,
+  can_weld: impl Fn(usize, usize) -> bool) -> FFIResult<usize> {
+    if epsilon <= 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    
+    let grid = SpatialGrid::new(&self.vertices, epsilon);
+    let mut target: Vec<u32> = (0..self.vertices.len() as u32).collect();
+    
+    for i in 0..self.vertices.len() {
+      let point = self.vertices[i];
+      
+      if let Some(j) = grid.nearby(&point).filter(|&j| {
+        (j as usize) < i && target[j as usize] == j &&
+        (self.vertices[j as usize] - point).norm() <= epsilon &&
+        can_weld(i, j as usize)
+      }).min() {
+        target[i] = j;
+      }
+    }
+    
+    let removed = target.iter().enumerate().filter(|&(i, &j)| {
+      i as u32 != j
+    }).count();
+    if removed == 0 { return Ok(0) };
+    
+    for index in self.triangles.iter_mut().flatten() {
+      *index = target[*index as usize];
+    }
+    self.triangles.retain(|triangle| {
+      triangle[0] != triangle[1] &&
+      triangle[1] != triangle[2] &&
+      triangle[2] != triangle[0]
+    });
+    
+    for index in self.edges.iter_mut().flatten() {
+      *index = target[*index as usize];
+    }
+    self.edges.retain(|edge| edge[0] != edge[1]);
+    
+    let keep: Vec<bool> = target.iter().enumerate().map(|(i, &j)| {
+      i as u32 == j
+    }).collect();
+    self.compact_vertices(&keep);
+    
+    return Ok(removed);
+  }
+  
+  /// Deletes triangles that repeat a vertex or have no area. Returns the number
+  /// deleted
+  pub fn delete_degenerate(&mut self) -> usize {
+    let before = self.triangles.len();
+    
+    let vertices = &self.vertices;
+    self.triangles.retain(|&triangle| {
+      let [a, b, c] = triangle.map(|i| vertices[i as usize]);
+      let longest = (b - a).norm().max((c - b).norm()).max((a - c).norm());
+      
+      (b - a).cross(&(c - a)).norm() > 1e-12*longest*longest
+    });
+    
+    let deleted = before - self.triangles.len();
+    if deleted > 0 && self.selection_type == SelectionType::TRIANGLES {
+      self.selection.drain(..);
+    }
+    
+    return deleted;
+  }
+  
+  /// Makes winding consistent across each connected piece, then flips any
+  /// piece whose signed volume is negative so it faces outward. Pieces are
+  /// found through shared edges. Open pieces are oriented the same way,
+  /// though for them "outward" is only a best guess
+  pub fn recalculate_outside(&mut self) {
+    let mut by_edge: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    for (i, t) in self.triangles.iter().enumerate() {
+      for j in 0..3 {
+        let (a, b) = (t[j], t[(j + 1) % 3]);
+        by_edge.entry((a.min(b), a.max(b))).or_default().push(i as u32);
+      }
+    }
+    
+    let has_directed = |t: [u32; 3], a: u32, b: u32| {
+      (0..3).any(|j| t[j] == a && t[(j + 1) % 3] == b)
+    };
+    
+    let mut visited = vec![false; self.triangles.len()];
+    for seed in 0..self.triangles.len() {
+      if visited[seed] { continue };
+      visited[seed] = true;
+      
+      let mut piece = vec![seed as u32];
+      let mut next = 0;
+      while next < piece.len() {
+        let t = self.triangles[piece[next] as usize];
+        next += 1;
+        
+        for j in 0..3 {
+          let (a, b) = (t[j], t[(j + 1) % 3]);
+          for &other in &by_edge[&(a.min(b), a.max(b))] {
+            if visited[other as usize] { continue };
+            visited[other as usize] = true;
+            
+            // A neighbor facing the same way runs the shared edge backward
+            let u = &mut self.triangles[other as usize];
+            if has_directed(*u, a, b) {
+              u.swap(1, 2);
+            }
+            piece.push(other);
+          }
+        }
+      }
+      
+      let count = (piece.len()*3) as f64;
+      let center = piece.iter().flat_map(|&i| self.triangles[i as usize])
+        .fold(V3::zeros(), |sum, i| sum + self.vertices[i as usize])/count;
+      let volume: f64 = piece.iter().map(|&i| {
+        let [a, b, c] = self.triangles[i as usize].map(|j| {
+          self.vertices[j as usize] - center
+        });
+        a.dot(&b.cross(&c))
+      }).sum();
+      
+      if volume < 0.0 {
+        for &i in &piece {
+          self.triangles[i as usize].swap(1, 2);
+        }
+      }
+    }
+  }
+  
+  /// Standard cleanup before export: welds vertices closer than 1e-6, deletes
+  /// degenerate triangles and vertices no longer used, and orients every
+  /// piece outward. Running it again on the result changes nothing
+  pub fn repair(&mut self) {
+    // Can .unwrap() because the epsilon is positive
+    self.remove_doubles(1e-6).unwrap();
+    self.delete_degenerate();
+    
+    let mut keep = vec![false; self.vertices.len()];
+    for &index in self.triangles.iter().flatten() {
+      keep[index as usize] = true;
+    }
+    for &index in self.edges.iter().flatten() {
+      keep[index as usize] = true;
+    }
+    if keep.contains(&false) {
+      self.compact_vertices(&keep);
+    }
+    
+    self.recalculate_outside();
+  }
+  
+  // Vertex deduplication
+  
+  /// Returns a list of vertices within the bounding box defined by the given
+  /// points. Allows error of 1e-6
+  pub fn select_vertices(&mut self, bound_1: V3<f64>, bound_2: V3<f64>) {
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    
+    let lower_bound = bound_1.inf(&bound_2) - V3::new(1e-6, 1e-6, 1e-6);
+    let upper_bound = bound_1.sup(&bound_2) + V3::new(1e-6, 1e-6, 1e-6);
+    
+    for i in 0..self.vertices.len() {
+      if lower_bound[0] < self.vertices[i][0] &&
+         self.vertices[i][0] < upper_bound[0] &&
+         lower_bound[1] < self.vertices[i][1] &&
+         self.vertices[i][1] < upper_bound[1] &&
+         lower_bound[2] < self.vertices[i][2] &&
+         self.vertices[i][2] < upper_bound[2] {
+        self.selection.push(i as u32);
+      }
+    }
+  }
+  
+  /// Returns a list of triangles within the bounding box defined by the given
+  /// points. Allows error of 1e-6
+  pub fn select_triangles(&mut self, bound_1: V3<f64>, bound_2: V3<f64>) {
+    self.select_vertices(bound_1, bound_2);
+    let bounded_vertices = self.selection.clone();
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::TRIANGLES;
+    
+    for i in 0..self.triangles.len() {
+      if bounded_vertices.contains(&self.triangles[i][0]) &&
+         bounded_vertices.contains(&self.triangles[i][1]) &&
+         bounded_vertices.contains(&self.triangles[i][2]) {
+        self.selection.push(i as u32);
+      }
+    }
+  }
+  
+  /// Automatically deletes affected triangles
+  pub fn delete_vertex(&mut self, vertex: u32) {
+    // Swap remove to avoid having to shift vertices
+    if let Some(colors) = self.colors.as_mut() {
+      colors.resize(self.vertices.len(), OPAQUE_WHITE);
+      colors.swap_remove(vertex as usize);
+    }
+    self.vertices.swap_remove(vertex as usize);
+    let swapped_vertex = self.vertices.len() as u32;
+    self.clear_vertex_attributes();
+    
+    // Delete triangles that include deleted vertex
+    self.triangles.retain(|triangle| !triangle.contains(&vertex));
+    
+    // Update indices if swapped vertex is referenced
+    for triangle in &mut self.triangles {
+      for index in triangle.iter_mut() {
+        if *index == swapped_vertex {
+          *index = vertex;
+        }
+      }
+    }
+    
+    self.edges.retain(|edge| !edge.contains(&vertex));
+    for edge in &mut self.edges {
+      for index in edge.iter_mut() {
+        if *index == swapped_vertex {
+          *index = vertex;
+        }
+      }
+    }
+    
+    self.creases = self.creases.drain().filter(|&((a, b), _)| {
+      a != vertex && b != vertex
+    }).map(|((a, b), weight)| {
+      let [a, b] = [a, b].map(|i| if i == swapped_vertex { vertex } else { i });
+      ((a.min(b), a.max(b)), weight)
+    }).collect();
+    
+    self.selection.drain(..);
+  }
+  
+  /// Automatically deletes affected triangles
+  pub fn delete_vertices(&mut self) {
+    // Vertices must be processed in reverse order, because deletion of lower-
+    // index vertices can change the index of higher-index vertices
+    self.selection.sort_unstable();
+    self.selection.reverse();
+    
+    for vertex in self.selection.clone() {
+      self.delete_vertex(vertex);
+    }
+  }
+  
+  pub fn delete_triangle(&mut self, triangle: u32) {
+    self.triangles.swap_remove(triangle as usize);
+    self.selection.drain(..);
+  }
+  
+  pub fn delete_triangles(&mut self) {
+    // Triangles must be processed in reverse order, because deletion of lower-
+    // index vertices can change the index of higher-index vertices
+    let mut selected = self.selected_triangles();
+    selected.sort_unstable();
+    selected.reverse();
+    
+    for triangle in selected {
+      self.delete_triangle(triangle);
+    }
+  }
+  
+  /// Deletes vertices not referenced by any triangle or edge
+  pub fn delete_stray_vertices(&mut self) {
+    let mut used = vec![false; self.vertices.len()];
+    for &index in self.triangles.iter().flatten() {
+      used[index as usize] = true;
+    }
+    for &index in self.edges.iter().flatten() {
+      used[index as usize] = true;
+    }
+    
+    // Vertices must be processed in reverse order, because deletion of lower-
+    // index vertices can change the index of higher-index vertices. Any vertex
+    // swapped into a deleted slot has already been checked and is used
+    for vertex in (0..self.vertices.len()).rev() {
+      if !used[vertex] {
+        self.delete_vertex(vertex as u32);
+      }
+    }
+  }
+  
+  pub fn new() -> Self {
+    Self {
+      vertices: Vec::new(),
+      triangles: Vec::new(),
+      edges: Vec::new(),
+      selection: Vec::new(),
+      selection_type: SelectionType::VERTICES,
+      normals: None,
+      uvs: None,
+      colors: None,
+      pivot: None,
+      smooth_groups: None,
+      creases: HashMap::new(),
+    }
+  }
+  
+  /// Sweeps a tube of the given radius along a closed path. Cross sections are
+  /// oriented with parallel transport frames, and any twist left over when the
+  /// path returns to its start is spread evenly along the tube so the seam
+  /// lines up. Selection becomes the new vertices
+  pub fn add_tube(&mut self, path: &[V3<f64>], sides: u32, radius: f64) {
+    let n = path.len();
+    let offset = self.vertices.len() as u32;
+    
+    let tangents: Vec<V3<f64>> = (0..n).map(|i| {
+      (path[(i + 1) % n] - path[(i + n - 1) % n]).normalize()
+    }).collect();
+    
+    // Any vector not parallel to the first tangent works as a starting normal
+    let mut normal = if tangents[0].x.abs() < 0.9 { V3::x() } else { V3::y() };
+    normal = (normal - tangents[0]*normal.dot(&tangents[0])).normalize();
+    
+    let mut normals = Vec::with_capacity(n);
+    for tangent in &tangents {
+      normal = (normal - tangent*normal.dot(tangent)).normalize();
+      normals.push(normal);
+    }
+    
+    // Transport the last normal back to the start to measure leftover twist
+    let closing = (normal - tangents[0]*normal.dot(&tangents[0])).normalize();
+    let twist = closing.cross(&normals[0]).dot(&tangents[0])
+      .atan2(closing.dot(&normals[0]));
+    
+    for i in 0..n {
+      let angle = twist*(i as f64)/(n as f64);
+      let binormal = tangents[i].cross(&normals[i]);
+      let normal = normals[i]*angle.cos() + binormal*angle.sin();
+      let binormal = tangents[i].cross(&normal);
+      
+      for j in 0..sides {
+        let theta = std::f64::consts::TAU*(j as f64)/(sides as f64);
+        self.vertices.push(path[i] + (normal*theta.cos() +
+          binormal*theta.sin())*radius);
+      }
+    }
+    
+    self.clear_vertex_attributes();
+    
+    for i in 0..n as u32 {
+      let next_i = (i + 1) % n as u32;
+      
+      for j in 0..sides {
+        let next_j = (j + 1) % sides;
+        
+        let a = offset + i*sides + j;
+        let b = offset + i*sides + next_j;
+        let c = offset + next_i*sides + j;
+        let d = offset + next_i*sides + next_j;
+        
+        self.triangles.push([a, b, c]);
+        self.triangles.push([b, d, c]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+  }
+  
+  /// Empties the geometry in place, as if newly created
+  pub fn clear(&mut self) {
+    *self = Self::new();
+  }
+  
+  /// Lays out UV islands side by side in the 0..1 range. An island is a set
+  /// of triangles connected through shared vertices, which is the same as
+  /// connected in UV space since UVs are stored per vertex.
+  ///
+  /// Each island is first scaled so its longer side is 1, keeping its aspect
+  /// ratio. Islands are then placed tallest first on shelves, left to right,
+  /// starting a new shelf when a row passes the square root of their total
+  /// area. `margin` is the gap between islands, in the same units as the
+  /// scaled islands, and the whole layout is finally scaled to fit 0..1.
+  /// Vertices not used by any triangle are left alone
+  pub fn uv_pack(&mut self, margin: f64) -> FFIResult<()> {
+    if margin < 0.0 { return Err(ErrorCode::ParameterOutOfRange) };
+    self.uvs_mut()?;
+    
+    let mut parent: Vec<u32> = (0..self.vertices.len() as u32).collect();
+    fn root(parent: &mut [u32], mut i: u32) -> u32 {
+      while parent[i as usize] != i {
+        parent[i as usize] = parent[parent[i as usize] as usize];
+        i = parent[i as usize];
+      }
+      return i;
+    }
+    for triangle in &self.triangles {
+      for j in 1..3 {
+        let a = root(&mut parent, triangle[0]);
+        let b = root(&mut parent, triangle[j]);
+        parent[a.max(b) as usize] = a.min(b);
+      }
+    }
+    
+    let mut used = vec![false; self.vertices.len()];
+    for &i in self.triangles.iter().flatten() {
+      used[i as usize] = true;
+    }
+    
+    let mut island_of: HashMap<u32, usize> = HashMap::new();
+    let mut islands: Vec<Vec<u32>> = Vec::new();
+    for i in 0..self.vertices.len() as u32 {
+      if !used[i as usize] { continue };
+      
+      let index = *island_of.entry(root(&mut parent, i)).or_insert_with(|| {
+        islands.push(Vec::new());
+        islands.len() - 1
+      });
+      islands[index].push(i);
+    }
+    
+    let uvs = self.uvs_mut()?;
+    
+    // Normalize each island, and record its scaled size
+    let mut sizes: Vec<[f64; 2]> = Vec::with_capacity(islands.len());
+    for island in &islands {
+      let mut min = [f64::MAX; 2];
+      let mut max = [f64::MIN; 2];
+      for &i in island {
+        for k in 0..2 {
+          min[k] = min[k].min(uvs[i as usize][k]);
+          max[k] = max[k].max(uvs[i as usize][k]);
+        }
+      }
+      
+      let longest = (max[0] - min[0]).max(max[1] - min[1]);
+      let scale = if longest > 0.0 { 1.0/longest } else { 1.0 };
+      for &i in island {
+        for k in 0..2 {
+          uvs[i as usize][k] = (uvs[i as usize][k] - min[k])*scale;
+        }
+      }
+      sizes.push([(max[0] - min[0])*scale, (max[1] - min[1])*scale]);
+    }
+    
+    let mut order: Vec<usize> = (0..islands.len()).collect();
+    order.sort_by(|&a, &b| sizes[b][1].total_cmp(&sizes[a][1]));
+    
+    let area: f64 = sizes.iter().map(|[w, h]| (w + margin)*(h + margin)).sum();
+    let widest = sizes.iter().map(|size| size[0]).fold(0.0, f64::max);
+    let row_width = area.sqrt().max(widest);
+    
+    let mut offsets = vec![[0.0; 2]; islands.len()];
+    let (mut x, mut y, mut shelf_height) = (0.0, 0.0, 0.0f64);
+    let mut extent = 0.0f64;
+    for i in order {
+      let [w, h] = sizes[i];
+      if x > 0.0 && x + w > row_width {
+        y += shelf_height + margin;
+        x = 0.0;
+        shelf_height = 0.0;
+      }
+      
+      offsets[i] = [x, y];
+      x += w + margin;
+      shelf_height = shelf_height.max(h);
+      extent = extent.max(x - margin).max(y + h);
+    }
+    
+    let scale = if extent > 0.0 { 1.0/extent } else { 1.0 };
+    for (island, offset) in islands.iter().zip(offsets) {
+      for &i in island {
+        for k in 0..2 {
+          uvs[i as usize][k] = (uvs[i as usize][k] + offset[k])*scale;
+        }
+      }
+    }
+    
+    return Ok(());
+  }
+  
+  /// Generates UVs automatically. Triangles are grouped into charts, cut
+  /// wherever the angle between neighboring faces exceeds `angle_threshold`
+  /// radians, and also wherever a chart would turn more than that from its
+  /// first triangle. Vertices on seams are duplicated so each chart has its
+  /// own. Each chart is then flattened by least squares conformal maps (see
+  /// flatten_chart()), starting from a projection onto the plane of its first
+  /// triangle. Finally the charts are laid out with uv_pack()
+  pub fn uv_unwrap(&mut self, angle_threshold: f64) -> FFIResult<()> {
+    if !(angle_threshold > 0.0 && angle_threshold < std::f64::consts::FRAC_PI_2)
+    {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    
+    let min_dot = angle_threshold.cos();
+    let normals: Vec<V3<f64>> = (0..self.triangles.len()).map(|i| {
+      self.face_normal(i)
+    }).collect();
+    
+    let mut by_edge: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    for (i, t) in self.triangles.iter().enumerate() {
+      for j in 0..3 {
+        let (a, b) = (t[j], t[(j + 1) % 3]);
+        by_edge.entry((a.min(b), a.max(b))).or_default().push(i as u32);
+      }
+    }
+    
+    let mut charts: Vec<Vec<u32>> = Vec::new();
+    let mut charted = vec![false; self.triangles.len()];
+    for seed in 0..self.triangles.len() {
+      if charted[seed] { continue };
+      charted[seed] = true;
+      
+      let mut chart = vec![seed as u32];
+      let mut next = 0;
+      while next < chart.len() {
+        let current = chart[next] as usize;
+        next += 1;
+        
+        let t = self.triangles[current];
+        for j in 0..3 {
+          let (a, b) = (t[j], t[(j + 1) % 3]);
+          for &other in &by_edge[&(a.min(b), a.max(b))] {
+            let normal = normals[other as usize];
+            if !charted[other as usize] &&
+            normal.dot(&normals[current]) >= min_dot &&
+            normal.dot(&normals[seed]) >= min_dot {
+              charted[other as usize] = true;
+              chart.push(other);
+            }
+          }
+        }
+      }
+      
+      charts.push(chart);
+    }
+    
+    // The first chart to use a vertex keeps it, later ones get copies
+    let vertex_count = self.vertices.len();
+    let mut sources: Vec<u32> = Vec::new();
+    let mut owner: Vec<Option<usize>> = vec![None; self.vertices.len()];
+    for (c, chart) in charts.iter().enumerate() {
+      let mut copies: HashMap<u32, u32> = HashMap::new();
+      
+      for &t in chart {
+        for k in 0..3 {
+          let v = self.triangles[t as usize][k];
+          match owner[v as usize] {
+            None => owner[v as usize] = Some(c),
+            Some(o) if o == c => {},
+            Some(_) => {
+              let copy = *copies.entry(v).or_insert_with(|| {
+                self.vertices.push(self.vertices[v as usize]);
+                sources.push(v);
+                (self.vertices.len() - 1) as u32
+              });
+              self.triangles[t as usize][k] = copy;
+            },
+          }
+        }
+      }
+    }
+    if self.vertices.len() > vertex_count {
+      self.copy_attributes(&sources);
+      self.clear_vertex_attributes();
+    }
+    
+    let mut uvs = vec![[0.0; 2]; self.vertices.len()];
+    for chart in &charts {
+      let normal = normals[chart[0] as usize];
+      let helper = if normal.x.abs() < 0.9 { V3::x() } else { V3::y() };
+      let Some(u_axis) = normal.cross(&helper).try_normalize(0.0) else {
+        continue
+      };
+      let v_axis = normal.cross(&u_axis);
+      
+      for &t in chart {
+        for v in self.triangles[t as usize] {
+          let position = self.vertices[v as usize];
+          uvs[v as usize] = [position.dot(&u_axis), position.dot(&v_axis)];
+        }
+      }
+      
+      self.flatten_chart(chart, &mut uvs);
+    }
+    self.uvs = Some(uvs);
+    
+    return self.uv_pack(0.02);
+  }
+  
+  /// Least squares conformal map (Lévy et al. 2002) of one chart of triangles,
+  /// which must not share vertices with other charts. `uvs` should hold a
+  /// rough flattening such as a planar projection: the two vertices furthest
+  /// apart along its U axis are pinned there, and the rest start from there.
+  /// Minimizes how far each triangle's mapping is from a similarity transform,
+  /// using conjugate gradients on the normal equations
+  fn flatten_chart(&self, chart: &[u32], uvs: &mut [[f64; 2]]) {
+    let mut local: HashMap<u32, usize> = HashMap::new();
+    let mut vertices: Vec<u32> = Vec::new();
+    for &t in chart {
+      for v in self.triangles[t as usize] {
+        local.entry(v).or_insert_with(|| {
+          vertices.push(v);
+          vertices.len() - 1
+        });
+      }
+    }
+    
+    let by_u = |&a: &usize, &b: &usize| {
+      uvs[vertices[a] as usize][0].total_cmp(&uvs[vertices[b] as usize][0])
+    };
+    let pins = [
+      (0..vertices.len()).min_by(by_u).unwrap_or(0),
+      (0..vertices.len()).max_by(by_u).unwrap_or(0),
+    ];
+    if pins[0] == pins[1] { return };
+    
+    // For each triangle, its local vertex indices and the complex coefficients
+    // W_j of the conformality condition, divided by sqrt(2*area). The
+    // condition is sum(W_j*(u_j + i*v_j)) = 0, each W_j being the edge
+    // opposite vertex j in the triangle's own plane
+    let mut rows = Vec::new();
+    for &t in chart {
+      let triangle = self.triangles[t as usize];
+      let [a, b, c] = triangle.map(|i| self.vertices[i as usize]);
+      let Some(x_axis) = (b - a).try_normalize(0.0) else { continue };
+      let normal = self.face_normal(t as usize);
+      if normal == V3::zeros() { continue };
+      let y_axis = normal.cross(&x_axis);
+      
+      let points = [a, b, c].map(|p| {
+        [(p - a).dot(&x_axis), (p - a).dot(&y_axis)]
+      });
+      let double_area = (b - a).cross(&(c - a)).norm();
+      let scale = 1.0/double_area.sqrt();
+      
+      let w = [0, 1, 2].map(|j| {
+        let (p, q) = (points[(j + 1) % 3], points[(j + 2) % 3]);
+        ((q[0] - p[0])*scale, (q[1] - p[1])*scale)
+      });
+      rows.push((triangle.map(|v| local[&v]), w));
+    }
+    
+    // Vectors interleave u and v. apply() computes A^T*A*x for the stacked
+    // real and imaginary parts of every triangle's condition
+    let apply = |x: &[f64], out: &mut Vec<f64>| {
+      out.clear();
+      out.resize(x.len(), 0.0);
+      for (indices, w) in &rows {
+        let (mut real, mut imaginary) = (0.0, 0.0);
+        for j in 0..3 {
+          let (u, v) = (x[2*indices[j]], x[2*indices[j] + 1]);
+          real += w[j].0*u - w[j].1*v;
+          imaginary += w[j].1*u + w[j].0*v;
+        }
+        for j in 0..3 {
+          out[2*indices[j]] += w[j].0*real + w[j].1*imaginary;
+          out[2*indices[j] + 1] += w[j].0*imaginary - w[j].1*real;
+        }
+      }
+      for pin in pins {
+        out[2*pin] = 0.0;
+        out[2*pin + 1] = 0.0;
+      }
+    };
+    
+    let mut x: Vec<f64> = vertices.iter().flat_map(|&v| uvs[v as usize])
+      .collect();
+    let mut product = Vec::new();
+    apply(&x, &mut product);
+    let mut residual: Vec<f64> = product.iter().map(|p| -p).collect();
+    let mut direction = residual.clone();
+    let mut residual_sq: f64 = residual.iter().map(|r| r*r).sum();
+    let tolerance = residual_sq*1e-20;
+    
+    for _ in 0..2*x.len() {
+      if residual_sq <= tolerance || residual_sq == 0.0 { break };
+      
+      apply(&direction, &mut product);
+      let curvature: f64 = direction.iter().zip(&product).map(|(d, p)| d*p)
+        .sum();
+      if curvature <= 0.0 { break };
+      
+      let step = residual_sq/curvature;
+      for i in 0..x.len() {
+        x[i] += step*direction[i];
+        residual[i] -= step*product[i];
+      }
+      
+      let next_sq: f64 = residual.iter().map(|r| r*r).sum();
+      for i in 0..x.len() {
+        direction[i] = residual[i] + next_sq/residual_sq*direction[i];
+      }
+      residual_sq = next_sq;
+    }
+    
+    for (i, &v) in vertices.iter().enumerate() {
+      uvs[v as usize] = [x[2*i], x[2*i + 1]];
+    }
+  }
+  
+  /// Parses Wavefront OBJ text. Only `v` and `f` lines are used, with faces of
+  /// more than three vertices split into fans. Texture and normal indices in
+  /// faces, and all other line types, are ignored
+  pub fn from_obj(text: &str) -> FFIResult<Self> {
+    let mut result = Self::new();
+    
+    for line in text.lines() {
+      let mut tokens = line.split_whitespace();
+      
+      match tokens.next() {
+        Some("v") => {
+          let mut coordinate = || -> FFIResult<f64> {
+            tokens.next().and_then(|token| token.parse().ok())
+              .ok_or(ErrorCode::ParseError)
+          };
+          let vertex = V3::new(coordinate()?, coordinate()?, coordinate()?);
+          result.vertices.push(vertex);
+        },
+        Some("f") => {
+          let count = result.vertices.len() as i64;
+          let face = tokens.map(|token| {
+            let index: i64 = token.split('/').next().unwrap().parse()
+              .map_err(|_| ErrorCode::ParseError)?;
+            
+            // OBJ indices start at 1, and negative ones count back from the
+            // latest vertex
+            let index = if index < 0 { count + index } else { index - 1 };
+            if index < 0 || index >= count {
+              return Err(ErrorCode::ParseError);
+            }
+            
+            return Ok(index as u32);
+          }).collect::<FFIResult<Vec<u32>>>()?;
+          
+          if face.len() < 3 { return Err(ErrorCode::ParseError) };
+          for i in 1..face.len() - 1 {
+            result.triangles.push([face[0], face[i], face[i + 1]]);
+          }
+        },
+        _ => {},
+      }
+    }
+    
+    return Ok(result);
+  }
+  
+  /// Adds a tube swept along a (p, q) torus knot. The knot winds p times
+  /// around the Z axis and q times through the hole of a torus with major
+  /// radius 1 and minor radius 0.5. `segments` is the number of cross sections
+  /// along the path. p and q must be coprime, because otherwise the path
+  /// retraces itself gcd(p, q) times
+  pub fn add_torus_knot(&mut self, p: u32, q: u32, segments: u32,
+  tube_radius: f64) -> FFIResult<()> {
+    let (mut a, mut b) = (p, q);
+    while b != 0 { (a, b) = (b, a % b) };
+    
+    if p == 0 || q == 0 || a != 1 || segments < 3 || tube_radius <= 0.0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit(segments as u64*TUBE_SIDES as u64)?;
+    
+    let path: Vec<V3<f64>> = (0..segments).map(|i| {
+      let phi = std::f64::consts::TAU*(i as f64)/(segments as f64);
+      let r = 1.0 + 0.5*(q as f64*phi).cos();
+      
+      V3::new(
+        r*(p as f64*phi).cos(),
+        r*(p as f64*phi).sin(),
+        -0.5*(q as f64*phi).sin(),
+      )
+    }).collect();
+    
+    self.add_tube(&path, TUBE_SIDES, tube_radius);
+    
+    return Ok(());
+  }
+  
+  /// Adds a solid staircase climbing along +X, with risers along +Z. The
+  /// first step starts at the origin and the stairs are centered on Y.
+  /// Selection becomes the new vertices
+  pub fn add_stairs(&mut self, steps: u32, width: f64, rise: f64, run: f64)
+  -> FFIResult<()> {
+    if steps == 0 || width <= 0.0 || rise <= 0.0 || run <= 0.0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit(2*(3*steps as u64 + 1))?;
+    
+    let offset = self.vertices.len() as u32;
+    let n = steps;
+    
+    // Each side profile has bottom points B(j) at x = j*run, plus for each
+    // step a top-left L(i) and top-right T(i) corner
+    for y in [-width/2.0, width/2.0] {
+      for j in 0..=n {
+        self.vertices.push(V3::new(j as f64*run, y, 0.0));
+      }
+      for i in 0..n {
+        let top = (i + 1) as f64*rise;
+        self.vertices.push(V3::new(i as f64*run, y, top));
+        self.vertices.push(V3::new((i + 1) as f64*run, y, top));
+      }
+    }
+    self.clear_vertex_attributes();
+    
+    let per_side = 3*n + 1;
+    let b = |side: u32, j: u32| offset + side*per_side + j;
+    let l = |side: u32, i: u32| offset + side*per_side + n + 1 + 2*i;
+    let t = |side: u32, i: u32| offset + side*per_side + n + 2 + 2*i;
+    
+    let mut faces: Vec<([u32; 3], V3<f64>)> = Vec::new();
+    let mut quad = |[p, q, r, s]: [u32; 4], outward: V3<f64>| {
+      faces.push(([p, q, r], outward));
+      faces.push(([p, r, s], outward));
+    };
+    
+    quad([b(0, n), t(0, n - 1), t(1, n - 1), b(1, n)], V3::x());
+    for i in 0..n {
+      quad([b(0, i), b(0, i + 1), b(1, i + 1), b(1, i)], -V3::z());
+      let below = if i == 0 { b(0, 0) } else { t(0, i - 1) };
+      let below_1 = if i == 0 { b(1, 0) } else { t(1, i - 1) };
+      quad([below, l(0, i), l(1, i), below_1], -V3::x());
+      quad([l(0, i), t(0, i), t(1, i), l(1, i)], V3::z());
+    }
+    
+    // Sides are fanned out from each column's bottom-right corner, so the
+    // previous step's corner on the left edge never makes a sliver
+    for (side, outward) in [(0, -V3::y()), (1, V3::y())] {
+      for i in 0..n {
+        let corner = b(side, i + 1);
+        faces.push(([corner, t(side, i), l(side, i)], outward));
+        if i == 0 {
+          faces.push(([corner, l(side, i), b(side, i)], outward));
+        } else {
+          faces.push(([corner, l(side, i), t(side, i - 1)], outward));
+          faces.push(([corner, t(side, i - 1), b(side, i)], outward));
+        }
+      }
+    }
+    
+    for ([p, q, r], outward) in faces {
+      let [vp, vq, vr] = [p, q, r].map(|i| self.vertices[i as usize]);
+      if (vq - vp).cross(&(vr - vp)).dot(&outward) >= 0.0 {
+        self.triangles.push([p, q, r]);
+      } else {
+        self.triangles.push([p, r, q]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Adds a surface of revolution around the Z axis. Each ring is a (radius,
+  /// z) pair, listed bottom to top. A ring of radius 0 becomes a single apex
+  /// vertex. Consecutive rings are joined by bands, and the first and last
+  /// rings can be capped with flat fans. Selection becomes the new vertices
+  fn add_revolved(&mut self, segments: u32, rings: &[(f64, f64)],
+  cap_bottom: bool, cap_top: bool) {
+    let offset = self.vertices.len() as u32;
+    
+    let mut starts = Vec::with_capacity(rings.len());
+    for &(radius, z) in rings {
+      starts.push(self.vertices.len() as u32);
+      
+      if radius == 0.0 {
+        self.vertices.push(V3::new(0.0, 0.0, z));
+      } else {
+        for k in 0..segments {
+          let angle = std::f64::consts::TAU*(k as f64)/(segments as f64);
+          self.vertices.push(V3::new(radius*angle.cos(), radius*angle.sin(),
+            z));
+        }
+      }
+    }
+    self.clear_vertex_attributes();
+    
+    // Apexes have one vertex, so every k maps to it
+    let at = |ring: usize, k: u32| -> u32 {
+      if rings[ring].0 == 0.0 { starts[ring] } else {
+        starts[ring] + k % segments
+      }
+    };
+    
+    for ring in 0..rings.len() - 1 {
+      for k in 0..segments {
+        let (l0, l1) = (at(ring, k), at(ring, k + 1));
+        let (u0, u1) = (at(ring + 1, k), at(ring + 1, k + 1));
+        
+        if l0 != l1 { self.triangles.push([l0, l1, u1]) };
+        if u0 != u1 { self.triangles.push([l0, u1, u0]) };
+      }
+    }
+    
+    let last = rings.len() - 1;
+    if cap_bottom && rings[0].0 != 0.0 {
+      for k in 1..segments - 1 {
+        self.triangles.push([at(0, 0), at(0, k + 1), at(0, k)]);
+      }
+    }
+    if cap_top && rings[last].0 != 0.0 {
+      for k in 1..segments - 1 {
+        self.triangles.push([at(last, 0), at(last, k), at(last, k + 1)]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+  }
+  
+  /// Adds a capped truncated cone along Z from -1 to 1. A top radius of 0
+  /// makes a cone, and equal radii make a cylinder. If `unit` is set, the
+  /// result is halved to fit the unit cube around the origin. Selection
+  /// becomes the new vertices
+  pub fn add_frustum(&mut self, segments: u32, bottom_radius: f64,
+  top_radius: f64, unit: bool) -> FFIResult<()> {
+    if segments < 3 || bottom_radius < 0.0 || top_radius < 0.0 ||
+    bottom_radius + top_radius == 0.0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit(2*segments as u64)?;
+    
+    let size = if unit { 0.5 } else { 1.0 };
+    self.add_revolved(segments, &[
+      (bottom_radius*size, -size),
+      (top_radius*size, size),
+    ], true, true);
+    
+    return Ok(());
+  }
+  
+  /// Adds a flat grid in the XY plane from -1 to 1, split into `x_divisions`
+  /// by `y_divisions` quads that share vertices, so there are
+  /// (x_divisions + 1)(y_divisions + 1) vertices in rows along X. Triangles
+  /// wind counterclockwise seen from +Z. If `unit` is set, the result is
+  /// halved to fit the unit square around the origin. Selection becomes the
+  /// new vertices
+  pub fn add_grid(&mut self, x_divisions: u32, y_divisions: u32, unit: bool)
+  -> FFIResult<()> {
+    if x_divisions == 0 || y_divisions == 0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit((x_divisions as u64 + 1)*(y_divisions as u64 + 1))?;
+    
+    let offset = self.vertices.len() as u32;
+    let size = if unit { 0.5 } else { 1.0 };
+    
+    for y in 0..=y_divisions {
+      for x in 0..=x_divisions {
+        self.vertices.push(V3::new(
+          size*(2.0*(x as f64)/(x_divisions as f64) - 1.0),
+          size*(2.0*(y as f64)/(y_divisions as f64) - 1.0),
+          0.0,
+        ));
+      }
+    }
+    self.clear_vertex_attributes();
+    
+    let index = |x: u32, y: u32| offset + y*(x_divisions + 1) + x;
+    for y in 0..y_divisions {
+      for x in 0..x_divisions {
+        let (a, b) = (index(x, y), index(x + 1, y));
+        let (c, d) = (index(x + 1, y + 1), index(x, y + 1));
+        self.triangles.push([a, b, c]);
+        self.triangles.push([a, c, d]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Adds a closed torus in the XY plane around the origin, with a major
+  /// radius of 1. Rings of `minor_segments` vertices are placed around the Z
+  /// axis, and both directions wrap back to their first ring, so there is no
+  /// seam. A minor radius near or above 1 makes the surface overlap itself
+  /// near the center, but the triangles stay valid. Selection becomes the new
+  /// vertices
+  pub fn add_torus(&mut self, major_segments: u32, minor_segments: u32,
+  minor_radius: f64) -> FFIResult<()> {
+    if major_segments < 3 || minor_segments < 3 || minor_radius.is_nan() ||
+    minor_radius <= 0.0 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit(major_segments as u64*minor_segments as u64)?;
+    
+    let offset = self.vertices.len() as u32;
+    let tau = 2.0*std::f64::consts::PI;
+    
+    for i in 0..major_segments {
+      let u = tau*(i as f64)/(major_segments as f64);
+      for j in 0..minor_segments {
+        let v = tau*(j as f64)/(minor_segments as f64);
+        let distance = 1.0 + minor_radius*v.cos();
+        self.vertices.push(V3::new(distance*u.cos(), distance*u.sin(),
+          minor_radius*v.sin()));
+      }
+    }
+    self.clear_vertex_attributes();
+    
+    let index = |i: u32, j: u32| {
+      offset + (i % major_segments)*minor_segments + j % minor_segments
+    };
+    for i in 0..major_segments {
+      for j in 0..minor_segments {
+        let (a, b) = (index(i, j), index(i + 1, j));
+        let (c, d) = (index(i + 1, j + 1), index(i, j + 1));
+        self.triangles.push([a, b, c]);
+        self.triangles.push([a, c, d]);
+      }
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Adds a capped cone along Z from -1 to 1, or a truncated cone if
+  /// `top_radius` is not 0. Same as add_frustum(): a zero radius end becomes a
+  /// single apex vertex with triangular sides, and `unit` halves the result to
+  /// fit the unit cube. Selection becomes the new vertices
+  pub fn add_cone(&mut self, segments: u32, bottom_radius: f64,
+  top_radius: f64, unit: bool) -> FFIResult<()> {
+    return self.add_frustum(segments, bottom_radius, top_radius, unit);
+  }
+  
+  /// Adds the side wall of a radius 1 cylinder along Z from -1 to 1, with no
+  /// caps, so its two open ends are ready for fill_holes() or bridging. If
+  /// `unit` is set, the result is halved to fit the unit cube around the
+  /// origin. Selection becomes the new vertices
+  pub fn add_cylinder_open(&mut self, segments: u32, unit: bool)
+  -> FFIResult<()> {
+    if segments < 3 { return Err(ErrorCode::ParameterOutOfRange) };
+    self.check_vertex_limit(2*segments as u64)?;
+    
+    let size = if unit { 0.5 } else { 1.0 };
+    self.add_revolved(segments, &[(size, -size), (size, size)], false, false);
+    
+    return Ok(());
+  }
+  
+  /// Adds extruded text in the XY plane, reading left to right along +X, from
+  /// z = 0 to z = `depth`. Glyph outlines come from a small bundled vector
+  /// font (see font_glyph()), are filled by ear clipping, and are extruded
+  /// with sweep(). `size` is the height of a capital letter. Each character
+  /// advances 6/7*`size`, and '\n' starts a new line 10/7*`size` below.
+  /// Lowercase letters are drawn as capitals. Other characters missing from
+  /// the font are skipped, leaving a space. Selection becomes the new vertices
+  pub fn add_text(&mut self, text: &str, size: f64, depth: f64)
+  -> FFIResult<()> {
+    if !(size > 0.0 && depth > 0.0) {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    
+    let scale = size/14.0;
+    let offset = self.vertices.len() as u32;
+    let mut points: Vec<V3<f64>> = Vec::new();
+    let mut triangles: Vec<[u32; 3]> = Vec::new();
+    let (mut column, mut line) = (0, 0);
+    for character in text.chars() {
+      if character == '\n' {
+        (column, line) = (0, line + 1);
+        continue;
+      }
+      
+      let origin = V3::new(column as f64*12.0, line as f64*-20.0, 0.0);
+      for shape in font_glyph(character.to_ascii_uppercase()).unwrap_or(&[]) {
+        let contours: Vec<Vec<V3<f64>>> = shape.iter().map(|contour| {
+          contour.iter().map(|&(x, y)| {
+            (origin + V3::new(x as f64, y as f64, 0.0))*scale
+          }).collect()
+        }).collect();
+        
+        let base = offset + points.len() as u32;
+        triangles.extend(triangulate_polygon(&contours).into_iter().map(|t| {
+          t.map(|i| base + i)
+        }));
+        points.extend(contours.into_iter().flatten());
+      }
+      column += 1;
+    }
+    
+    self.check_vertex_limit(2*points.len() as u64)?;
+    self.vertices.extend(points);
+    self.clear_vertex_attributes();
+    
+    let first_triangle = self.triangles.len() as u32;
+    self.triangles.extend(triangles);
+    self.selection.drain(..);
+    self.selection_type = SelectionType::TRIANGLES;
+    self.selection.extend(first_triangle..self.triangles.len() as u32);
+    
+    self.sweep(1, false, |_, point| point + V3::new(0.0, 0.0, depth));
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Adds a UV sphere of radius 1 around the origin, with `rings` bands from
+  /// pole to pole and `segments` quads around each band. Each pole is a single
+  /// vertex joined to the nearest ring by a triangle fan. Each ring repeats its
+  /// first vertex at the end, so the seam has separate vertices on each side
+  /// for per-vertex data like UVs. The sphere is therefore open along the seam
+  /// until welded. Selection becomes the new vertices
+  pub fn add_sphere(&mut self, rings: u32, segments: u32) -> FFIResult<()> {
+    if rings < 2 || segments < 3 {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit((rings as u64 - 1)*(segments as u64 + 1) + 2)?;
+    
+    let offset = self.vertices.len() as u32;
+    let pi = std::f64::consts::PI;
+    
+    self.vertices.push(V3::new(0.0, 0.0, 1.0));
+    for i in 1..rings {
+      let polar = pi*(i as f64)/(rings as f64);
+      
+      for j in 0..=segments {
+        // The last vertex of each ring is placed exactly on the first
+        let azimuth = 2.0*pi*((j % segments) as f64)/(segments as f64);
+        self.vertices.push(V3::new(polar.sin()*azimuth.cos(),
+          polar.sin()*azimuth.sin(), polar.cos()));
+      }
+    }
+    self.vertices.push(V3::new(0.0, 0.0, -1.0));
+    self.clear_vertex_attributes();
+    
+    let ring = |i: u32, j: u32| offset + 1 + (i - 1)*(segments + 1) + j;
+    let bottom = self.vertices.len() as u32 - 1;
+    for j in 0..segments {
+      self.triangles.push([offset, ring(1, j), ring(1, j + 1)]);
+      
+      for i in 1..rings - 1 {
+        let (a, b) = (ring(i, j), ring(i, j + 1));
+        let (c, d) = (ring(i + 1, j), ring(i + 1, j + 1));
+        self.triangles.push([a, c, d]);
+        self.triangles.push([a, d, b]);
+      }
+      
+      self.triangles.push([bottom, ring(rings - 1, j + 1),
+        ring(rings - 1, j)]);
+    }
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+    
+    return Ok(());
+  }
+  
+  /// Adds the part of a unit sphere between two polar angles, measured from
+  /// the +Z pole, so (0, π/2) is the top hemisphere. `rings` is the number of
+  /// bands between the two angles. If `cap` is set, open ends are closed with
+  /// flat fans. Selection becomes the new vertices
+  pub fn add_sphere_section(&mut self, segments: u32, rings: u32,
+  start_angle: f64, end_angle: f64, cap: bool) -> FFIResult<()> {
+    if segments < 3 || rings == 0 || !(0.0 <= start_angle &&
+    start_angle < end_angle && end_angle <= std::f64::consts::PI) {
+      return Err(ErrorCode::ParameterOutOfRange);
+    }
+    self.check_vertex_limit((rings as u64 + 1)*segments as u64)?;
+    
+    // Listed bottom to top, so from end_angle back to start_angle. Poles are
+    // set exactly, since sin(π) is not quite 0
+    let profile: Vec<(f64, f64)> = (0..=rings).rev().map(|i| {
+      let angle = start_angle + (end_angle - start_angle)*(i as f64)/
+        (rings as f64);
+      
+      if angle == 0.0 { (0.0, 1.0) }
+      else if angle == std::f64::consts::PI { (0.0, -1.0) }
+      else { (angle.sin(), angle.cos()) }
+    }).collect();
+    
+    self.add_revolved(segments, &profile, cap, cap);
+    
+    return Ok(());
+  }
+  
+  /// Copies another geometry's vertices and triangles into this one.
+  /// Selection becomes the incoming vertices
+  pub fn join(&mut self, other: &Geometry) {
+    let offset = self.vertices.len() as u32;
+    
+    self.join_keep_selection(other);
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend(offset..self.vertices.len() as u32);
+  }
+  
+  /// Same as .join(), but moves the incoming vertices by `offset`
+  pub fn append(&mut self, other: &Geometry, offset: V3<f64>) {
+    let start = self.vertices.len();
+    
+    self.join(other);
+    
+    for vertex in &mut self.vertices[start..] {
+      *vertex += offset;
+    }
+  }
+  
+  /// Builds a new geometry with one copy of `item` at the centroid of each
+  /// triangle of this one. If `align_to_normal` is set, each copy is rotated
+  /// so its +Z axis points along the triangle's normal. Degenerate triangles
+  /// have no normal, so their copies are left unrotated
+  pub fn scatter(&self, item: &Geometry, align_to_normal: bool)
+  -> FFIResult<Geometry> {
+    let mut result = Geometry::new();
+    result.check_vertex_limit((item.vertices.len() as u64).saturating_mul(
+      self.triangles.len() as u64))?;
+    
+    for i in 0..self.triangles.len() {
+      let centroid = self.triangles[i].iter().fold(V3::zeros(), |sum, &j| {
+        sum + self.vertices[j as usize]
+      })/3.0;
+      let normal = self.face_normal(i);
+      
+      let rotation = if align_to_normal && normal != V3::zeros() {
+        Rotation3::rotation_between(&V3::z(), &normal).unwrap_or_else(|| {
+          Rotation3::from_axis_angle(&V3::x_axis(), std::f64::consts::PI)
+        })
+      } else {
+        Rotation3::identity()
+      };
+      
+      let mut copy = item.clone();
+      for vertex in &mut copy.vertices {
+        *vertex = centroid + rotation*(*vertex);
+      }
+      result.join_keep_selection(&copy);
+    }
+    
+    return Ok(result);
+  }
+  
+  /// Same as .join(), but leaves the existing selection alone so the original
+  /// part can still be edited
+  pub fn join_keep_selection(&mut self, other: &Geometry) {
+    let offset = self.vertices.len() as u32;
+    
+    if self.colors.is_some() || other.colors.is_some() {
+      let mut colors = self.colors.take().unwrap_or_default();
+      colors.resize(offset as usize, OPAQUE_WHITE);
+      colors.extend((0..other.vertices.len()).map(|i| {
+        other.colors.iter().flat_map(|colors| colors.get(i)).next().copied()
+          .unwrap_or(OPAQUE_WHITE)
+      }));
+      self.colors = Some(colors);
+    }
+    
+    self.vertices.extend_from_slice(&other.vertices);
+    self.clear_vertex_attributes();
+    self.triangles.extend(other.triangles.iter().map(|triangle| {
+      [triangle[0] + offset, triangle[1] + offset, triangle[2] + offset]
+    }));
+    self.creases.extend(other.creases.iter().map(|(&(a, b), &weight)| {
+      ((a + offset, b + offset), weight)
+    }));
+    self.edges.extend(other.edges.iter().map(|edge| {
+      [edge[0] + offset, edge[1] + offset]
+    }));
+  }
+  
+  /// Replaces this geometry with its union with `other`. Both must be closed
+  /// and convex, which is checked (within a small tolerance) since non-convex
+  /// input would give wrong results. Each surface is clipped by the face
+  /// planes of the other, keeping what lies outside, and the pieces are welded
+  /// together. Where the two surfaces lie flat against each other facing the
+  /// same way, this geometry's copy is kept. Edges are kept, but vertex
+  /// attributes and selection are cleared
+  pub fn union_convex(&mut self, other: &Geometry) -> FFIResult<()> {
+    if !self.is_closed() || !other.is_closed() {
+      return Err(ErrorCode::NotClosed);
+    }
+    
+    let (min, max) = self.vertices.iter().chain(&other.vertices).fold(
+      (V3::repeat(f64::INFINITY), V3::repeat(f64::NEG_INFINITY)),
+      |(min, max), v| (min.inf(v), max.sup(v)));
+    let size = (max - min).norm().max(f64::MIN_POSITIVE);
+    let epsilon = size*1e-9;
+    
+    let own_planes = self.convex_planes(size*1e-6).ok_or(
+      ErrorCode::NotConvex)?;
+    let other_planes = other.convex_planes(size*1e-6).ok_or(
+      ErrorCode::NotConvex)?;
+    
+    let mut pieces = self.pieces_outside(&other_planes, true, epsilon);
+    pieces.extend(other.pieces_outside(&own_planes, false, epsilon));
+    
+    self.triangles.drain(..);
+    for piece in pieces {
+      let base = self.vertices.len() as u32;
+      self.vertices.extend_from_slice(&piece);
+      for i in 1..piece.len() as u32 - 1 {
+        self.triangles.push([base, base + i, base + i + 1]);
+      }
+    }
+    self.clear_vertex_attributes();
+    self.creases.clear();
+    
+    self.remove_doubles(size*1e-7)?;
+    self.split_t_junctions(size*1e-7);
+    
+    let mut keep = vec![false; self.vertices.len()];
+    let used = self.triangles.iter().flatten().chain(self.edges.iter()
+      .flatten());
+    for &i in used {
+      keep[i as usize] = true;
+    }
+    self.compact_vertices(&keep);
+    
+    return Ok(());
+  }
+  
+  /// Unit normals and offsets (normal·x = offset) of the distinct face planes
+  /// of a convex mesh, or None if a vertex lies more than `tolerance` in front
+  /// of any of them
+  fn convex_planes(&self, tolerance: f64) -> Option<Vec<(V3<f64>, f64)>> {
+    let mut planes: Vec<(V3<f64>, f64)> = Vec::new();
+    
+    for i in 0..self.triangles.len() {
+      let normal = self.face_normal(i);
+      if normal == V3::zeros() { continue };
+      let offset = normal.dot(&self.vertices[self.triangles[i][0] as usize]);
+      
+      if self.vertices.iter().any(|v| normal.dot(v) - offset > tolerance) {
+        return None;
+      }
+      
+      if !planes.iter().any(|&(n, o)| {
+        n.dot(&normal) > 1.0 - 1e-9 && (o - offset).abs() <= tolerance
+      }) {
+        planes.push((normal, offset));
+      }
+    }
+    
+    return Some(planes);
+  }
+  
+  /// Convex polygons covering the parts of this surface in front of at least
+  /// one of `planes`. A part lying in one of the planes counts as in front of
+  /// it only if `keep_coplanar` is set and it faces the same way
+  fn pieces_outside(&self, planes: &[(V3<f64>, f64)], keep_coplanar: bool,
+  epsilon: f64) -> Vec<Vec<V3<f64>>> {
+    let mut pieces = Vec::new();
+    
+    for i in 0..self.triangles.len() {
+      let normal = self.face_normal(i);
+      let mut remaining: Vec<V3<f64>> = self.triangles[i].iter().map(|&j| {
+        self.vertices[j as usize]
+      }).collect();
+      
+      for &(plane_normal, offset) in planes {
+        if remaining.is_empty() { break };
+        
+        if remaining.iter().all(|p| {
+          (plane_normal.dot(p) - offset).abs() <= epsilon
+        }) {
+          if keep_coplanar && normal.dot(&plane_normal) > 0.0 {
+            pieces.push(std::mem::take(&mut remaining));
+          }
+          continue;
+        }
+        
+        let (outside, inside) = Self::split_polygon(&remaining, plane_normal,
+          offset, epsilon);
+        if outside.len() >= 3 { pieces.push(outside) };
+        remaining = if inside.len() >= 3 { inside } else { Vec::new() };
+      }
+    }
+    
+    return pieces;
+  }
+  
+  /// Splits a convex polygon by the plane normal·x = offset into the parts in
+  /// front of and behind it. Points within `epsilon` of the plane go to both
+  /// parts, and a part with no point clearly on its side is left empty
+  fn split_polygon(polygon: &[V3<f64>], normal: V3<f64>, offset: f64,
+  epsilon: f64) -> (Vec<V3<f64>>, Vec<V3<f64>>) {
+    let distances: Vec<f64> = polygon.iter().map(|p| {
+      normal.dot(p) - offset
+    }).collect();
+    
+    let mut front = Vec::new();
+    let mut back = Vec::new();
+    for i in 0..polygon.len() {
+      let j = (i + 1) % polygon.len();
+      let (p, q) = (polygon[i], polygon[j]);
+      let (dp, dq) = (distances[i], distances[j]);
+      
+      if dp >= -epsilon { front.push(p) };
+      if dp <= epsilon { back.push(p) };
+      if (dp > epsilon && dq < -epsilon) || (dp < -epsilon && dq > epsilon) {
+        let crossing = p + (q - p)*(dp/(dp - dq));
+        front.push(crossing);
+        back.push(crossing);
+      }
+    }
+    
+    if !distances.iter().any(|&d| d > epsilon) { front.clear() };
+    if !distances.iter().any(|&d| d < -epsilon) { back.clear() };
+    
+    return (front, back);
+  }
+  
+  /// Where a vertex lies on an open edge of a triangle without being one of
+  /// its corners, splits the triangle so the vertex becomes shared
+  fn split_t_junctions(&mut self, epsilon: f64) {
+    loop {
+      let all: Vec<u32> = (0..self.triangles.len() as u32).collect();
+      let open = self.boundary_edges(&all);
+      if open.is_empty() { return };
+      
+      let mut candidates: Vec<u32> = open.iter().flatten().copied().collect();
+      candidates.sort_unstable();
+      candidates.dedup();
+      
+      let mut split = false;
+      for [a, b] in open {
+        let (pa, pb) = (self.vertices[a as usize], self.vertices[b as usize]);
+        let length_squared = (pb - pa).norm_squared();
+        if length_squared == 0.0 { continue };
+        
+        let mut between: Vec<(f64, u32)> = candidates.iter().filter_map(|&v| {
+          let point = self.vertices[v as usize];
+          let t = (point - pa).dot(&(pb - pa))/length_squared;
+          let on_edge = v != a && v != b && t > 0.0 && t < 1.0 &&
+            (pa + (pb - pa)*t - point).norm() <= epsilon;
+          on_edge.then_some((t, v))
+        }).collect();
+        if between.is_empty() { continue };
+        between.sort_by(|x, y| x.0.total_cmp(&y.0));
+        
+        let Some(i) = self.triangles.iter().position(|t| {
+          (0..3).any(|j| t[j] == a && t[(j + 1) % 3] == b)
+        }) else { continue };
+        let t = self.triangles[i];
+        let c = t[(0..3).find(|&j| t[j] != a && t[j] != b).unwrap_or(0)];
+        
+        let mut chain = vec![a];
+        chain.extend(between.iter().map(|&(_, v)| v));
+        chain.push(b);
+        self.triangles[i] = [chain[0], chain[1], c];
+        for pair in chain[1..].windows(2) {
+          self.triangles.push([pair[0], pair[1], c]);
+        }
+        
+        split = true;
+        break;
+      }
+      
+      if !split { return };
+    }
+  }
+  
+  /// Adds a line segment between two new vertices. Selection becomes the new
+  /// vertices
+  pub fn add_line(&mut self, a: V3<f64>, b: V3<f64>) {
+    let offset = self.vertices.len() as u32;
+    
+    self.vertices.push(a);
+    self.vertices.push(b);
+    self.clear_vertex_attributes();
+    self.edges.push([offset, offset + 1]);
+    
+    self.selection.drain(..);
+    self.selection_type = SelectionType::VERTICES;
+    self.selection.extend([offset, offset + 1]);
+  }
+  
+  pub fn cube() -> Self {
+    Self {
+      vertices: vec![
+        V3::new(-1.0,  1.0, -1.0),
+        V3::new(-1.0,  1.0,  1.0),
+        
+        V3::new(-1.0, -1.0, -1.0),
+        V3::new(-1.0, -1.0,  1.0),
+        
+        V3::new( 1.0,  1.0, -1.0),
+        V3::new( 1.0,  1.0,  1.0),
+        
+        V3::new( 1.0, -1.0, -1.0),
+        V3::new( 1.0, -1.0,  1.0),
+      ],
+      triangles: vec![
+        // Top
+        [1, 3, 5],
+        [3, 7, 5],
+        
+        // +X side
+        [4, 5, 6],
+        [5, 7, 6],
+        
+        // -X side
+        [0, 2, 1],
+        [1, 2, 3],
+        
+        // +Y side
+        [0, 1, 4],
+        [1, 5, 4],
+        
+        // -Y side
+        [2, 6, 3],
+        [3, 6, 7],
+        
+        // Bottom
+        [0, 4, 2],
+        [2, 4, 6],
+      ],
+      edges: Vec::new(),
+      selection: Vec::new(),
+      selection_type: SelectionType::VERTICES,
+      normals: None,
+      uvs: None,
+      colors: None,
+      pivot: None,
+      smooth_groups: None,
+      creases: HashMap::new(),
+    }
+  }
+  
+  pub fn pack(&self, gltf: &mut GLTF) -> PackedGeometry {
+    if gltf.buffer_per_mesh {
+      gltf.new_buffer();
+    }
+    
+    // Calculate vertex bounds. The vertex bounds are f32 because that is the
+    // same precision as GLTF vertices
+    let mut min = V3::repeat(f32::MAX);
+    let mut max = V3::repeat(f32::MIN);
+    let pivot = self.pivot.unwrap_or(V3::zeros());
+    for vertex in &self.vertices {
+      let vertex = vertex - pivot;
+      let vertex = V3::new(vertex.x as f32, vertex.y as f32, vertex.z as f32);
+      min = min.inf(&vertex);
+      max = max.sup(&vertex);
+    }
+    
+    gltf.append_to_glb_bin(self.vertices_raw(), Type::VEC3,
+      ComponentType::Float);
+    // Can .unwrap() because the previous .append_to_glb_bin() call guarantees
+    // .accessors/min/max will be populated
+    gltf.accessors.last_mut().unwrap().min.extend_from_slice(min.as_slice());
+    gltf.accessors.last_mut().unwrap().max.extend_from_slice(max.as_slice());
+    gltf.buffer_views.last_mut().unwrap().target = Some(
+      Target::ArrayBuffer);
+    
+    let vertex_buffer = gltf.accessors.len() as u32 - 1;
+    
+    // Normals are skipped if vertices were added or removed after they were
+    // computed
+    let mut normal_buffer = None;
+    if self.normals.as_ref().is_some_and(|normals| {
+      normals.len() == self.vertices.len()
+    }) {
+      gltf.append_to_glb_bin(self.normals_raw(), Type::VEC3,
+        ComponentType::Float);
+      gltf.buffer_views.last_mut().unwrap().target = Some(
+        Target::ArrayBuffer);
+      normal_buffer = Some(gltf.accessors.len() as u32 - 1);
+    }
+    
+    let mut uv_buffer = None;
+    if self.uvs.as_ref().is_some_and(|uvs| uvs.len() == self.vertices.len()) {
+      gltf.append_to_glb_bin(self.uvs_raw(), Type::VEC2, ComponentType::Float);
+      gltf.buffer_views.last_mut().unwrap().target = Some(
+        Target::ArrayBuffer);
+      uv_buffer = Some(gltf.accessors.len() as u32 - 1);
+    }
+    
+    let mut color_buffer = None;
+    if self.colors.is_some() {
+      gltf.append_to_glb_bin(self.colors_raw(), Type::VEC4,
+        ComponentType::Float);
+      gltf.buffer_views.last_mut().unwrap().target = Some(
+        Target::ArrayBuffer);
+      color_buffer = Some(gltf.accessors.len() as u32 - 1);
+    }
+    
+    // A geometry made only of lines does not get a triangle primitive
+    let mut triangle_buffer = None;
+    if !self.triangles.is_empty() || self.edges.is_empty() {
+      gltf.append_to_glb_bin(self.triangles_raw(), Type::SCALAR,
+        self.triangles_raw_component_type());
+      gltf.buffer_views.last_mut().unwrap().target = Some(
+        Target::ElementArrayBuffer);
+      triangle_buffer = Some(gltf.accessors.len() as u32 - 1);
+    }
+    
+    let mut edge_buffer = None;
+    if !self.edges.is_empty() {
+      gltf.append_to_glb_bin(self.edges_raw(), Type::SCALAR,
+        self.triangles_raw_component_type());
+      gltf.buffer_views.last_mut().unwrap().target = Some(
+        Target::ElementArrayBuffer);
+      edge_buffer = Some(gltf.accessors.len() as u32 - 1);
+    }
+    
+    return PackedGeometry {
+      vertex_buffer,
+      normal_buffer,
+      uv_buffer,
+      color_buffer,
+      triangle_buffer,
+      edge_buffer,
+      pivot: self.pivot,
+    }
+  }
+}
+
+#[derive(Copy, Clone)]
+pub struct PackedGeometry {
+  vertex_buffer: u32,
+  normal_buffer: Option<u32>,
+  uv_buffer: Option<u32>,
+  color_buffer: Option<u32>,
+  triangle_buffer: Option<u32>,
+  edge_buffer: Option<u32>,
+  pivot: Option<V3<f64>>,
+}
+
+/// Deterministic 3D value noise in -1 to 1: a pseudorandom value at each
+/// integer lattice point, blended between them with smoothstep. Only integer
+/// hashing and basic float arithmetic are used, so every platform gives the
+/// same result
+fn value_noise(point: V3<f64>, seed: u32) -> f64 {
+  let lattice = |x: i64, y: i64, z: i64| -> f64 {
+    let mut h = (x as u64).wrapping_mul(0x9E3779B97F4A7C15) ^
+      (y as u64).wrapping_mul(0xC2B2AE3D27D4EB4F) ^
+      (z as u64).wrapping_mul(0x165667B19E3779F9) ^
+      (seed as u64).wrapping_mul(0x27D4EB2F165667C5);
+    
+    // Finalizer from MurmurHash3
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51AFD7ED558CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CEB9FE1A85EC53);
+    h ^= h >> 33;
+    
+    return (h >> 11) as f64/(1u64 << 53) as f64*2.0 - 1.0;
+  };
+  
+  let base = point.map(f64::floor);
+  let t = (point - base).map(|t| t*t*(3.0 - 2.0*t));
+  let [x, y, z] = [base.x as i64, base.y as i64, base.z as i64];
+  let lerp = |a: f64, b: f64, t: f64| a + (b - a)*t;
+  
+  let mut face = [0.0; 2];
+  for (k, value) in face.iter_mut().enumerate() {
+    let z = z + k as i64;
+    *value = lerp(
+      lerp(lattice(x, y, z), lattice(x + 1, y, z), t.x),
+      lerp(lattice(x, y + 1, z), lattice(x + 1, y + 1, z), t.x),
+      t.y,
+    );
+  }
+  
+  return lerp(face[0], face[1], t.z);
+}
+
+/// Color of vertices that have not been given one
+const OPAQUE_WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Glyph outlines for add_text(). Each glyph is a list of shapes, and each
+/// shape a list of contours: the outline first, then any holes. Points are on
+/// a grid 10 wide with capitals 14 tall and strokes 2 thick, y up from the
+/// baseline. Winding does not matter, triangulate_polygon() fixes it
+type Glyph = &'static [&'static [&'static [(i8, i8)]]];
+
+/// Outlines of space, A to Z, 0 to 9, and some punctuation. Lowercase and any
+/// other characters give None
+fn font_glyph(character: char) -> Option<Glyph> {
+  let glyph: Glyph = match character {
+    ' ' => &[],
+    'A' => &[&[
+      &[(0, 0), (2, 0), (2, 5), (8, 5), (8, 0), (10, 0), (10, 11), (7, 14),
+        (3, 14), (0, 11)],
+      &[(2, 7), (8, 7), (8, 10), (6, 12), (4, 12), (2, 10)],
+    ]],
+    'B' => &[&[
+      &[(0, 0), (8, 0), (10, 2), (10, 6), (9, 7), (10, 8), (10, 12), (8, 14),
+        (0, 14)],
+      &[(2, 2), (7, 2), (8, 3), (8, 5), (7, 6), (2, 6)],
+      &[(2, 8), (7, 8), (8, 9), (8, 11), (7, 12), (2, 12)],
+    ]],
+    'C' => &[&[&[(2, 0), (10, 0), (10, 2), (3, 2), (2, 3), (2, 11), (3, 12),
+      (10, 12), (10, 14), (2, 14), (0, 12), (0, 2)]]],
+    'D' => &[&[
+      &[(0, 0), (7, 0), (10, 3), (10, 11), (7, 14), (0, 14)],
+      &[(2, 2), (6, 2), (8, 4), (8, 10), (6, 12), (2, 12)],
+    ]],
+    'E' => &[&[&[(0, 0), (10, 0), (10, 2), (2, 2), (2, 6), (8, 6), (8, 8),
+      (2, 8), (2, 12), (10, 12), (10, 14), (0, 14)]]],
+    'F' => &[&[&[(0, 0), (2, 0), (2, 6), (8, 6), (8, 8), (2, 8), (2, 12),
+      (10, 12), (10, 14), (0, 14)]]],
+    'G' => &[&[&[(2, 0), (10, 0), (10, 8), (5, 8), (5, 6), (8, 6), (8, 2),
+      (3, 2), (2, 3), (2, 11), (3, 12), (10, 12), (10, 14), (2, 14), (0, 12),
+      (0, 2)]]],
+    'H' => &[&[&[(0, 0), (2, 0), (2, 6), (8, 6), (8, 0), (10, 0), (10, 14),
+      (8, 14), (8, 8), (2, 8), (2, 14), (0, 14)]]],
+    'I' => &[&[&[(2, 0), (8, 0), (8, 2), (6, 2), (6, 12), (8, 12), (8, 14),
+      (2, 14), (2, 12), (4, 12), (4, 2), (2, 2)]]],
+    'J' => &[&[&[(2, 0), (8, 0), (10, 2), (10, 14), (8, 14), (8, 3), (7, 2),
+      (3, 2), (2, 3), (2, 5), (0, 5), (0, 2)]]],
+    'K' => &[&[&[(0, 0), (2, 0), (2, 5), (7, 0), (10, 0), (3, 7), (10, 14),
+      (7, 14), (2, 9), (2, 14), (0, 14)]]],
+    'L' => &[&[&[(0, 0), (10, 0), (10, 2), (2, 2), (2, 14), (0, 14)]]],
+    'M' => &[&[&[(0, 0), (2, 0), (2, 11), (5, 8), (8, 11), (8, 0), (10, 0),
+      (10, 14), (8, 14), (5, 11), (2, 14), (0, 14)]]],
+    'N' => &[&[&[(0, 0), (2, 0), (2, 10), (8, 0), (10, 0), (10, 14), (8, 14),
+      (8, 4), (2, 14), (0, 14)]]],
+    'O' => &[&[
+      &[(2, 0), (8, 0), (10, 2), (10, 12), (8, 14), (2, 14), (0, 12), (0, 2)],
+      &[(3, 2), (7, 2), (8, 3), (8, 11), (7, 12), (3, 12), (2, 11), (2, 3)],
+    ]],
+    'P' => &[&[
+      &[(0, 0), (2, 0), (2, 6), (8, 6), (10, 8), (10, 12), (8, 14), (0, 14)],
+      &[(2, 8), (7, 8), (8, 9), (8, 11), (7, 12), (2, 12)],
+    ]],
+    'Q' => &[&[
+      &[(2, 0), (10, 0), (10, 12), (8, 14), (2, 14), (0, 12), (0, 2)],
+      &[(3, 2), (5, 2), (8, 5), (8, 11), (7, 12), (3, 12), (2, 11), (2, 3)],
+    ]],
+    'R' => &[&[
+      &[(0, 0), (2, 0), (2, 6), (5, 6), (8, 0), (10, 0), (7, 6), (8, 6),
+        (10, 8), (10, 12), (8, 14), (0, 14)],
+      &[(2, 8), (7, 8), (8, 9), (8, 11), (7, 12), (2, 12)],
+    ]],
+    'S' => &[&[&[(0, 0), (8, 0), (10, 2), (10, 6), (8, 8), (2, 8), (2, 12),
+      (10, 12), (10, 14), (2, 14), (0, 12), (0, 8), (2, 6), (8, 6), (8, 2),
+      (0, 2)]]],
+    'T' => &[&[&[(4, 0), (6, 0), (6, 12), (10, 12), (10, 14), (0, 14),
+      (0, 12), (4, 12)]]],
+    'U' => &[&[&[(2, 0), (8, 0), (10, 2), (10, 14), (8, 14), (8, 3), (7, 2),
+      (3, 2), (2, 3), (2, 14), (0, 14), (0, 2)]]],
+    'V' => &[&[&[(4, 0), (6, 0), (10, 14), (8, 14), (5, 3), (2, 14),
+      (0, 14)]]],
+    'W' => &[&[&[(2, 0), (4, 0), (5, 5), (6, 0), (8, 0), (10, 14), (8, 14),
+      (7, 5), (6, 9), (4, 9), (3, 5), (2, 14), (0, 14)]]],
+    'X' => &[&[&[(0, 0), (2, 0), (5, 5), (8, 0), (10, 0), (6, 7), (10, 14),
+      (8, 14), (5, 9), (2, 14), (0, 14), (4, 7)]]],
+    'Y' => &[&[&[(4, 0), (6, 0), (6, 6), (10, 14), (8, 14), (5, 8), (2, 14),
+      (0, 14), (4, 6)]]],
+    'Z' => &[&[&[(0, 0), (10, 0), (10, 2), (3, 2), (10, 12), (10, 14), (0, 14),
+      (0, 12), (7, 12), (0, 2)]]],
+    '0' => &[&[
+      &[(2, 0), (8, 0), (10, 2), (10, 12), (8, 14), (2, 14), (0, 12), (0, 2)],
+      &[(4, 2), (7, 2), (8, 3), (8, 10)],
+      &[(2, 3), (6, 11), (6, 12), (3, 12), (2, 11)],
+    ]],
+    '1' => &[&[&[(2, 0), (8, 0), (8, 2), (6, 2), (6, 14), (4, 14), (1, 11),
+      (2, 9), (4, 11), (4, 2), (2, 2)]]],
+    '2' => &[&[&[(0, 0), (10, 0), (10, 2), (3, 2), (10, 9), (10, 12), (8, 14),
+      (2, 14), (0, 12), (0, 10), (2, 10), (2, 11), (3, 12), (7, 12), (8, 11),
+      (8, 10), (0, 2)]]],
+    '3' => &[&[&[(0, 0), (8, 0), (10, 2), (10, 6), (9, 7), (10, 8), (10, 12),
+      (8, 14), (0, 14), (0, 12), (7, 12), (8, 11), (8, 9), (7, 8), (3, 8),
+      (3, 6), (7, 6), (8, 5), (8, 3), (7, 2), (0, 2)]]],
+    '4' => &[&[
+      &[(6, 0), (8, 0), (8, 4), (10, 4), (10, 6), (8, 6), (8, 14), (6, 14),
+        (0, 6), (0, 4), (6, 4)],
+      &[(3, 6), (6, 6), (6, 10)],
+    ]],
+    '5' => &[&[&[(0, 0), (8, 0), (10, 2), (10, 7), (8, 9), (2, 9), (2, 12),
+      (10, 12), (10, 14), (0, 14), (0, 7), (7, 7), (8, 6), (8, 3), (7, 2),
+      (0, 2)]]],
+    '6' => &[&[
+      &[(2, 0), (8, 0), (10, 2), (10, 7), (8, 9), (2, 9), (2, 11), (3, 12),
+        (10, 12), (10, 14), (2, 14), (0, 12), (0, 2)],
+      &[(3, 2), (7, 2), (8, 3), (8, 6), (7, 7), (2, 7), (2, 3)],
+    ]],
+    '7' => &[&[&[(3, 0), (5, 0), (10, 12), (10, 14), (0, 14), (0, 12),
+      (8, 12)]]],
+    '8' => &[&[
+      &[(2, 0), (8, 0), (10, 2), (10, 6), (9, 7), (10, 8), (10, 12), (8, 14),
+        (2, 14), (0, 12), (0, 8), (1, 7), (0, 6), (0, 2)],
+      &[(3, 2), (7, 2), (8, 3), (8, 5), (7, 6), (3, 6), (2, 5), (2, 3)],
+      &[(3, 8), (7, 8), (8, 9), (8, 11), (7, 12), (3, 12), (2, 11), (2, 9)],
+    ]],
+    '9' => &[&[
+      &[(2, 0), (8, 0), (10, 2), (10, 12), (8, 14), (2, 14), (0, 12), (0, 7),
+        (2, 5), (8, 5), (8, 3), (7, 2), (0, 2)],
+      &[(3, 7), (8, 7), (8, 11), (7, 12), (3, 12), (2, 11), (2, 8)],
+    ]],
+    '.' => &[&[&[(4, 0), (6, 0), (6, 2), (4, 2)]]],
+    ',' => &[&[&[(3, -2), (4, -2), (6, 0), (6, 2), (4, 2), (4, 0)]]],
+    ':' => &[
+      &[&[(4, 2), (6, 2), (6, 4), (4, 4)]],
+      &[&[(4, 8), (6, 8), (6, 10), (4, 10)]],
+    ],
+    ';' => &[
+      &[&[(3, -2), (4, -2), (6, 0), (6, 2), (4, 2), (4, 0)]],
+      &[&[(4, 8), (6, 8), (6, 10), (4, 10)]],
+    ],
+    '!' => &[
+      &[&[(4, 0), (6, 0), (6, 2), (4, 2)]],
+      &[&[(4, 4), (6, 4), (6, 14), (4, 14)]],
+    ],
+    '?' => &[
+      &[&[(4, 0), (6, 0), (6, 2), (4, 2)]],
+      &[&[(4, 4), (6, 4), (6, 6), (10, 8), (10, 12), (8, 14), (2, 14),
+        (0, 12), (0, 10), (2, 10), (2, 11), (3, 12), (7, 12), (8, 11), (8, 9),
+        (4, 7)]],
+    ],
+    '-' => &[&[&[(2, 6), (8, 6), (8, 8), (2, 8)]]],
+    '+' => &[&[&[(4, 3), (6, 3), (6, 6), (9, 6), (9, 8), (6, 8), (6, 11),
+      (4, 11), (4, 8), (1, 8), (1, 6), (4, 6)]]],
+    '=' => &[
+      &[&[(1, 4), (9, 4), (9, 6), (1, 6)]],
+      &[&[(1, 8), (9, 8), (9, 10), (1, 10)]],
+    ],
+    '_' => &[&[&[(0, -2), (10, -2), (10, 0), (0, 0)]]],
+    '\'' => &[&[&[(4, 10), (6, 10), (6, 14), (4, 14)]]],
+    '"' => &[
+      &[&[(2, 10), (4, 10), (4, 14), (2, 14)]],
+      &[&[(6, 10), (8, 10), (8, 14), (6, 14)]],
+    ],
+    '/' => &[&[&[(0, 0), (2, 0), (10, 14), (8, 14)]]],
+    '(' => &[&[&[(5, 0), (7, 0), (5, 3), (5, 11), (7, 14), (5, 14), (3, 11),
+      (3, 3)]]],
+    ')' => &[&[&[(3, 0), (5, 0), (7, 3), (7, 11), (5, 14), (3, 14), (5, 11),
+      (5, 3)]]],
+    _ => return None,
+  };
+  
+  return Some(glyph);
+}
+
+/// Ear-clipping triangulation of a polygon in the XY plane. `contours[0]` is
+/// the outline and any others are holes inside it. Each hole is first joined
+/// to the outline by a two-way bridge, giving one polygon that visits the
+/// bridge ends twice. Returns counterclockwise triangles indexing the points
+/// of all contours in order
+fn triangulate_polygon(contours: &[Vec<V3<f64>>]) -> Vec<[u32; 3]> {
+  let mut starts = vec![0];
+  for contour in contours {
+    starts.push(starts[starts.len() - 1] + contour.len());
+  }
+  let points: Vec<V3<f64>> = contours.iter().flatten().copied().collect();
+  let cross = |o: usize, a: usize, b: usize| -> f64 {
+    let (a, b) = (points[a] - points[o], points[b] - points[o]);
+    return a.x*b.y - a.y*b.x;
+  };
+  let signed_area = |ring: &[usize]| -> f64 {
+    return (0..ring.len()).map(|i| {
+      cross(0, ring[i], ring[(i + 1) % ring.len()])
+    }).sum::<f64>();
+  };
+  
+  // Outline counterclockwise, holes clockwise
+  let mut rings: Vec<Vec<usize>> = (0..contours.len()).map(|c| {
+    let mut ring: Vec<usize> = (starts[c]..starts[c + 1]).collect();
+    if (signed_area(&ring) > 0.0) != (c == 0) { ring.reverse() };
+    ring
+  }).collect();
+  let mut polygon = rings.remove(0);
+  
+  // Holes reaching furthest right are bridged first, so each bridge runs
+  // right from its hole to the outline or an already bridged hole
+  let rightmost = |ring: &Vec<usize>| -> usize {
+    return *ring.iter().max_by(|&&a, &&b| {
+      points[a].x.total_cmp(&points[b].x)
+        .then(points[b].y.total_cmp(&points[a].y))
+    }).unwrap();
+  };
+  rings.sort_by(|a, b| {
+    points[rightmost(b)].x.total_cmp(&points[rightmost(a)].x)
+  });
+  
+  for hole in rings {
+    let m = rightmost(&hole);
+    let origin = points[m];
+    
+    // Nearest edge crossed by a ray going +X from the hole
+    let mut best: Option<(f64, usize)> = None;
+    for i in 0..polygon.len() {
+      let a = points[polygon[i]];
+      let b = points[polygon[(i + 1) % polygon.len()]];
+      if (a.y > origin.y) == (b.y > origin.y) { continue };
+      
+      let x = a.x + (origin.y - a.y)*(b.x - a.x)/(b.y - a.y);
+      if x < origin.x || best.is_some_and(|(best_x, _)| best_x <= x) {
+        continue;
+      }
+      // Bridge to the edge's right end, as the nearer end may be hidden
+      let end = if a.x > b.x { i } else { (i + 1) % polygon.len() };
+      best = Some((x, end));
+    }
+    let Some((hit_x, mut target)) = best else { continue };
+    
+    // A reflex vertex inside the triangle between the ray and the chosen end
+    // could block the bridge, so take the one closest in angle to the ray
+    let hit = V3::new(hit_x, origin.y, 0.0);
+    let end = points[polygon[target]];
+    let mut best_angle = f64::MAX;
+    for i in 0..polygon.len() {
+      let p = points[polygon[i]];
+      let before = polygon[(i + polygon.len() - 1) % polygon.len()];
+      let after = polygon[(i + 1) % polygon.len()];
+      if i == target || cross(before, polygon[i], after) >= 0.0 { continue };
+      if !point_in_triangle(p, origin, hit, end) { continue };
+      
+      let angle = (p.y - origin.y).abs().atan2(p.x - origin.x);
+      if angle < best_angle {
+        best_angle = angle;
+        target = i;
+      }
+    }
+    
+    // The target may already end another bridge, so appear more than once.
+    // Use the copy whose corner opens toward the hole
+    let n = polygon.len();
+    let toward = origin - points[polygon[target]];
+    let turn = |from: V3<f64>, to: V3<f64>| -> f64 {
+      return (from.x*to.y - from.y*to.x).atan2(from.dot(&to))
+        .rem_euclid(std::f64::consts::TAU);
+    };
+    if let Some(j) = (0..n).filter(|&j| polygon[j] == polygon[target])
+    .find(|&j| {
+      let corner = points[polygon[j]];
+      let before = points[polygon[(j + n - 1) % n]] - corner;
+      let after = points[polygon[(j + 1) % n]] - corner;
+      turn(after, toward) < turn(after, before)
+    }) {
+      target = j;
+    }
+    
+    let start = hole.iter().position(|&i| i == m).unwrap();
+    let mut spliced = polygon[..=target].to_vec();
+    spliced.extend(hole[start..].iter().chain(&hole[..=start]));
+    spliced.extend(&polygon[target..]);
+    polygon = spliced;
+  }
+  
+  let mut triangles = Vec::new();
+  let mut misses = 0;
+  let mut i = 0;
+  while polygon.len() > 3 {
+    let n = polygon.len();
+    let (a, b, c) = (polygon[(i + n - 1) % n], polygon[i % n],
+      polygon[(i + 1) % n]);
+    
+    // Polygons from the font have no overlaps, so if a full lap finds no ear
+    // it is only rounding, and any convex corner will do
+    let turn = cross(a, b, c);
+    let is_ear = turn > 0.0 && (misses > n || !polygon.iter().any(|&p| {
+      let point = points[p];
+      point != points[a] && point != points[b] && point != points[c] &&
+        point_in_triangle(point, points[a], points[b], points[c])
+    }));
+    
+    // Bridges can leave a vertex doubled back on itself, which is dropped
+    let is_spike = points[a] == points[b] || points[b] == points[c] ||
+      points[a] == points[c];
+    
+    if is_spike || is_ear {
+      if is_ear { triangles.push([a as u32, b as u32, c as u32]) };
+      polygon.remove(i % n);
+      misses = 0;
+    } else {
+      i += 1;
+      misses += 1;
+      if misses > 2*n { break };
+    }
+  }
+  if polygon.len() == 3 && cross(polygon[0], polygon[1], polygon[2]) > 0.0 {
+    triangles.push([polygon[0] as u32, polygon[1] as u32, polygon[2] as u32]);
+  }
+  
+  return triangles;
+}
+
+/// Whether `p` is inside or on the edge of triangle `abc`, of either winding,
+/// ignoring Z
+fn point_in_triangle(p: V3<f64>, a: V3<f64>, b: V3<f64>, c: V3<f64>) -> bool {
+  let side = |o: V3<f64>, d: V3<f64>| (d.x - o.x)*(p.y - o.y) -
+    (d.y - o.y)*(p.x - o.x);
+  let sides = [side(a, b), side(b, c), side(c, a)];
+  return sides.iter().all(|&s| s >= 0.0) || sides.iter().all(|&s| s <= 0.0);
+}
+
+/////////////////////////
+// GLTF Data Structure //
+/////////////////////////
+
+#[derive(Clone, serde::Serialize)]
+pub struct Asset {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub copyright: String,
+  
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub generator: String,
+  
+  // Don't skip if empty...this field is mandatory per GLTF spec!
+  pub version: String,
+  
+  #[serde(skip_serializing_if = "String::is_empty")]
+  #[serde(rename = "minVersion")]
+  pub min_version: String,
+  
+  // pub extensions: ??,
+  
+  // In the .gltf spec, but will have to wait for later
+  //pub extra: ??,
+}
+
+impl Asset {
+  pub fn new() -> Self {
+    Self {
+      copyright: String::from(""),
+      generator: String::from("emg v0.1.0"),
+      version: String::from("2.0"),
+      min_version: String::from("2.0"),
+    }
+  }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct GLTF {
+  // Don't skip if empty...this field is mandatory per GLTF spec!
+  pub asset: Asset,
+  
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scene: Option<u32>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub scenes: Vec<Scene>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub nodes: Vec<Node>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub materials: Vec<Material>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub meshes: Vec<Mesh>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub accessors: Vec<Accessor>,
+  
+  #[serde(rename = "bufferViews")]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub buffer_views: Vec<BufferView>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub buffers: Vec<Buffer>,
+  
+  // TODO Not sure about the memory use effects of putting all GLB BIN data
+  // into one vector during model construction. Look into using a
+  // Vec<Vec<u8>> or similar when I have a suitable test setup
+  #[serde(skip_serializing)]
+  pub glb_bin: Vec<u8>,
+  
+  /// Data for buffers after the first. GLB has only one BIN chunk, which
+  /// belongs to buffer 0, so serialize() folds these into it
+  #[serde(skip_serializing)]
+  pub extra_bins: Vec<Vec<u8>>,
+  
+  /// If set, each packed geometry starts a new buffer so a loader can fetch
+  /// meshes separately
+  #[serde(skip_serializing)]
+  pub buffer_per_mesh: bool,
+  
+  // In the .gltf spec, but will have to wait for later
+  /*pub animations: ??
+   *  pub asset: ??
+   *  pub extensionsUsed: ??
+   *  pub extensionsRequired: ??
+   *  pub cameras: ??
+   *  pub images: ??
+   *  pub samplers: ??
+   *  pub skins: ??
+   *  pub textures: ??
+   *  pub extensions: ??
+   *  pub extras: ??*/
+}
+
+impl GLTF {
+  pub fn new() -> Self {
+    let scene = Scene::new("A name for a scene");
+    
+    Self {
+      asset: Asset::new(),
+      nodes: Vec::new(),
+      materials: Vec::new(),
+      scene: Some(0),
+      scenes: vec![scene],
+      meshes: Vec::new(),
+      accessors: Vec::new(),
+      buffer_views: Vec::new(),
+      buffers: vec!(Buffer::new("")),
+      glb_bin: Vec::new(),
+      extra_bins: Vec::new(),
+      buffer_per_mesh: false,
+    }
+  }
+  
+  /// Directs later .append_to_glb_bin() calls to a new buffer. Does nothing if
+  /// the current buffer is still empty
+  pub fn new_buffer(&mut self) {
+    if self.buffers.last().is_some_and(|buffer| buffer.byte_length == 0) {
+      return;
+    }
+    
+    self.buffers.push(Buffer::new(""));
+    self.extra_bins.push(Vec::new());
+  }
+  
+  /// GLB allows only buffer 0 to use the BIN chunk, so this concatenates the
+  /// data of every buffer, each starting on a 4-byte boundary, and returns it
+  /// with a copy of this GLTF whose buffer views are shifted into buffer 0.
+  /// The per-mesh buffers are left as they are
+  pub fn merge_buffers(&mut self) -> (GLTF, Vec<u8>) {
+    let glb_bin = std::mem::take(&mut self.glb_bin);
+    let extra_bins = std::mem::take(&mut self.extra_bins);
+    let mut merged = self.clone();
+    
+    let mut bin = glb_bin.clone();
+    let mut starts = vec![0];
+    for data in &extra_bins {
+      bin.resize(bin.len().next_multiple_of(4), 0);
+      starts.push(bin.len() as u32);
+      bin.extend_from_slice(data);
+    }
+    
+    for buffer_view in &mut merged.buffer_views {
+      buffer_view.byte_offset += starts[buffer_view.buffer as usize];
+      buffer_view.buffer = 0;
+    }
+    merged.buffers.truncate(1);
+    merged.buffers[0].byte_length = bin.len() as u32;
+    
+    self.glb_bin = glb_bin;
+    self.extra_bins = extra_bins;
+    return (merged, bin);
+  }
+  
+  pub fn append_to_glb_bin(&mut self, buffer: impl IntoIterator,
+  type_: Type, component_type: ComponentType) {
+    // Data always goes to the latest buffer
+    let data = self.extra_bins.last_mut().unwrap_or(&mut self.glb_bin);
+    let buffer_index = self.buffers.len() - 1;
+    
+    let mut bytes = 0;
+    for value in buffer.into_iter() {
+      let sliced = unsafe { any_as_u8_slice(&value) };
+      data.extend_from_slice(sliced);
+      bytes += sliced.len() as u32;
+    }
+    self.buffers[buffer_index].byte_length += bytes;
+    
+    let mut buffer_view = BufferView::new("");
+    buffer_view.buffer = buffer_index as u32;
+    buffer_view.byte_length = bytes;
+    buffer_view.byte_offset = (data.len() as u32) - bytes;
+    self.buffer_views.push(buffer_view);
+    
+    let mut accessor = Accessor::new("");
+    accessor.buffer_view = Some((self.buffer_views.len() - 1) as u32);
+    accessor.type_ = type_;
+    accessor.component_type = component_type;
+    accessor.count = bytes/type_.component_count()/component_type.byte_count();
+    self.accessors.push(accessor);
+  }
+  
+  /// Creates a new node and adds it to the specified scene. If unsure, use
+  /// scene 0
+  pub fn new_root_node<S: Into<String>>(&mut self, scene: u32, name: S) ->
+  *mut Node {
+    let index = self.nodes.len() as u32;
+    self.scenes[scene as usize].nodes.push(index);
+    self.nodes.push(Node::new(name));
+    self.nodes.last_mut().unwrap()
+  }
+  
+  /// Creates a new node and adds it to the specified node
+  pub fn new_node<S: Into<String>>(&mut self, node: u32, name: S) -> &mut Node {
+    let index = self.nodes.len() as u32;
+    self.nodes[node as usize].children.push(index);
+    self.nodes.push(Node::new(name));
+    self.nodes.last_mut().unwrap()
+  }
+  
+  /// Creates a new mesh and adds it to the specified node
+  pub fn new_mesh<S: Into<String>>(&mut self, node: u32, name: S) -> &mut Mesh {
+  let index = self.meshes.len() as u32;
+    self.nodes[node as usize].mesh = Some(index);
+    self.meshes.push(Mesh::new(name));
+    self.meshes.last_mut().unwrap()
+  }
+  
+  pub fn new_material<S: Into<String>>(&mut self, name: S) -> &mut Material {
+    self.materials.push(Material::new(name));
+    
+    // .unwrap() here doesn't unwrap .material, but instead unwraps the result
+    // of calling .as_mut(), and is permissible because .material is guaranteed
+    // to have a value after the previous line
+    self.materials.last_mut().unwrap()
+  }
+}
+
+// WARNING: Do not edit!
+//
+// Found this function here:
+// https://stackoverflow.com/questions/28127165/how-to-convert-struct-to-u8
+//
+// Getting something into raw bytes in Rust is absurdly overcomplicated. Code
+// that does this is densely packed with subtle dangers, hidden complications,
+// and unpleasant surprises. Do not attempt to edit it.
+unsafe fn any_as_u8_slice<T: Sized>(p: &T) -> &[u8] {
+  ::core::slice::from_raw_parts(
+    (p as *const T) as *const u8,
+    ::core::mem::size_of::<T>(),
+  )
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Scene {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub nodes: Vec<u32>,
+  
+  //pub extensions: Vec<??>,
+  
+  // In the .gltf spec but not currently used:
+  //pub extras: Vec<A JSON-serializable struct>,
+}
+
+impl Scene {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self { name: name.into(), nodes: Vec::new() }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+#[derive(serde_tuple::Serialize_tuple)]
+pub struct Translation {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+}
+
+impl Translation {
+  pub fn new() -> Self { Self { x: 0.0, y: 0.0, z: 0.0 } }
+  pub fn is_default(&self) -> bool { *self == Self::new() }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+#[derive(serde_tuple::Serialize_tuple)]
+pub struct Rotation {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+  pub w: f64,
+}
+
+impl Rotation {
+  pub fn new() -> Self { Self { x: 0.0, y: 0.0, z: 0.0, w: 1.0 } }
+  pub fn is_default(&self) -> bool { *self == Self::new() }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+#[derive(serde_tuple::Serialize_tuple)]
+pub struct Scale {
+  pub x: f64,
+  pub y: f64,
+  pub z: f64,
+}
+
+impl Scale {
+  pub fn new() -> Self { Self { x: 1.0, y: 1.0, z: 1.0 } }
+  pub fn is_default(&self) -> bool { *self == Self::new() }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Node {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mesh: Option<u32>,
+  
+  #[serde(rename = "translation")]
+  #[serde(skip_serializing_if = "Translation::is_default")]
+  pub t: Translation,
+  
+  #[serde(rename = "rotation")]
+  #[serde(skip_serializing_if = "Rotation::is_default")]
+  pub r: Rotation,
+  
+  #[serde(rename = "scale")]
+  #[serde(skip_serializing_if = "Scale::is_default")]
+  pub s: Scale,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub children: Vec<u32>,
+  
+  //pub mesh: ??,
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub camera: ??,
+   *  pub skin: ??,
+   *  pub matrix: ??,
+   *  pub weights: ??,
+   *  pub extras: ??,*/
+}
+
+impl Node {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self {
+      name: name.into(),
+      mesh: None,
+      t: Translation::new(),
+      r: Rotation::new(),
+      s: Scale::new(),
+      children: Vec::new(),
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, serde::Serialize)]
+pub enum AlphaMode {
+  OPAQUE,
+  MASK,
+  BLEND,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+#[derive(serde_tuple::Serialize_tuple)]
+pub struct Color4 {
+  pub r: f64,
+  pub g: f64,
+  pub b: f64,
+  pub a: f64,
+}
+
+impl Color4 {
+  pub fn new() -> Self { Self { r: 1.0, g: 1.0, b: 1.0, a: 1.0 } }
+  pub fn is_default(&self) -> bool { *self == Self::new() }
+}
+
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct PBRMetallicRoughness {
+  #[serde(rename = "baseColorFactor")]
+  #[serde(skip_serializing_if = "Color4::is_default")]
+  pub base_color_factor: Color4,
+  
+  #[serde(rename = "metallicFactor")]
+  #[serde(skip_serializing_if = "is_default_metallic_factor")]
+  pub metallic_factor: f64,
+  
+  #[serde(rename = "roughnessFactor")]
+  #[serde(skip_serializing_if = "is_default_roughness_factor")]
+  pub roughness_factor: f64,
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub extras: ??,
+   *  pub metallicRoughnessTexture: ??,
+   *  pub baseColorTexture: ??,
+   */
+}
+
+impl PBRMetallicRoughness {
+  pub fn new() -> Self {
+    Self {
+      base_color_factor: Color4::new(),
+      metallic_factor: 1.0,
+      roughness_factor: 1.0,
+    }
+  }
+}
+
+fn is_default_metallic_factor(value: &f64) -> bool {
+  *value == 1.0
+}
+
+fn is_default_roughness_factor(value: &f64) -> bool {
+  *value == 1.0
+}
+
+fn is_default_emissive_factor(value: &[f64; 3]) -> bool {
+  *value == [0.0, 0.0, 0.0]
+}
+
+fn is_default_alpha_mode(value: &AlphaMode) -> bool {
+  *value == AlphaMode::OPAQUE
+}
+
+fn is_default_alpha_cutoff(value: &f64) -> bool {
+  *value == 0.5
+}
+
+fn is_default_double_sided(value: &bool) -> bool {
+  *value == false
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Material {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  #[serde(rename = "emissiveFactor")]
+  #[serde(skip_serializing_if = "is_default_emissive_factor")]
+  pub emissive_factor: [f64; 3],
+  
+  #[serde(rename = "alphaMode")]
+  #[serde(skip_serializing_if = "is_default_alpha_mode")]
+  pub alpha_mode: AlphaMode,
+  
+  #[serde(rename = "alphaCutoff")]
+  #[serde(skip_serializing_if = "is_default_alpha_cutoff")]
+  pub alpha_cutoff: f64,
+  
+  #[serde(rename = "doubleSided")]
+  #[serde(skip_serializing_if = "is_default_double_sided")]
+  pub double_sided: bool,
+  
+  #[serde(rename = "pbrMetallicRoughness")]
+  // Not sure how to skip serializing when unused for this one
+  pub pbr_metallic_roughness: PBRMetallicRoughness,
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub extras: ??,
+   *  pub normalTexture: ??,
+   *  pub occlusionTexture: ??,
+   *  pub emissiveTexture: ??,*/
+}
+
+impl Material {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self {
+      name: name.into(),
+      emissive_factor: [0.0, 0.0, 0.0],
+      alpha_mode: AlphaMode::OPAQUE,
+      alpha_cutoff: 0.5,
+      double_sided: false,
+      pbr_metallic_roughness: PBRMetallicRoughness::new(),
+    }
+  }
+}
+
+// The fields here are in the spec in section 3.7 - Concepts / Geometry,
+// which took me a while to find
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct Attributes {
+  #[serde(rename = "COLOR_0")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub color_0: Option<u32>,
+  
+  #[serde(rename = "JOINTS_0")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub joints_0: Option<u32>,
+  
+  #[serde(rename = "NORMAL")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub normal: Option<u32>,
+  
+  #[serde(rename = "POSITION")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub position: Option<u32>,
+  
+  #[serde(rename = "TANGENT")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tangent: Option<u32>,
+  
+  #[serde(rename = "TEXCOORD_0")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub texcoord_0: Option<u32>,
+  
+  #[serde(rename = "TEXCOORD_1")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub texcoord_1: Option<u32>,
+  
+  #[serde(rename = "TEXCOORD_2")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub texcoord_2: Option<u32>,
+  
+  #[serde(rename = "TEXCOORD_3")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub texcoord_3: Option<u32>,
+  
+  #[serde(rename = "WEIGHTS_0")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub weights_0: Option<u32>,
+}
+
+impl Attributes {
+  pub fn new() -> Self {
+    Self {
+      color_0: None,
+      joints_0: None,
+      normal: None,
+      position: None,
+      tangent: None,
+      texcoord_0: None,
+      texcoord_1: None,
+      texcoord_2: None,
+      texcoord_3: None,
+      weights_0: None,
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, serde_repr::Serialize_repr)]
+#[repr(u8)]
+pub enum Mode {
+  Points = 0,
+  Lines = 1,
+  LineLoop = 2,
+  LineStrip = 3,
+  Triangles = 4,
+  TriangleStrip = 5,
+  TriangleFan = 6,
+}
+
+fn is_default_mode(value: &Mode) -> bool {
+  *value == Mode::Triangles
+}
+
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct MeshPrimitive {
+  pub attributes: Attributes,
+  
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub indices: Option<u32>,
+  
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub material: Option<u32>,
+  
+  #[serde(skip_serializing_if = "is_default_mode")]
+  pub mode: Mode, // Default is triangles
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub extras: ??,
+   *  pub targets: ??,*/
+}
+
+impl MeshPrimitive {
+  pub fn new() -> Self {
+    Self {
+      attributes: Attributes::new(),
+      indices: None,
+      material: None,
+      mode: Mode::Triangles,
+    }
+  }
+  
+  /// Set material index
+  pub fn material(&mut self, material: u32) -> &mut Self {
+    self.material = Some(material);
+    self
+  }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Mesh {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  // No serialization filter, this is required per spec
+  pub primitives: Vec<MeshPrimitive>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub weights: Vec<f64>,
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub extras: ??,*/
+}
+
+impl Mesh {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self {
+      name: name.into(),
+      primitives: Vec::new(),
+      weights: Vec::new(),
+    }
+  }
+  
+  pub fn copy_primitive(&mut self, primitive: MeshPrimitive) ->
+  &mut MeshPrimitive {
+    self.primitives.push(primitive);
+    self.primitives.last_mut().unwrap()
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, serde_repr::Serialize_repr)]
+#[repr(u16)]
+pub enum ComponentType {
+  Byte = 5120,
+  UnsignedByte = 5121,
+  Short = 5122,
+  UnsignedShort = 5123,
+  UnsignedInt = 5125,
+  Float = 5126,
+}
+
+impl ComponentType {
+  pub fn byte_count(&self) -> u32 {
+    match self {
+      Self::Byte          => 1,
+      Self::UnsignedByte  => 1,
+      Self::Short         => 2,
+      Self::UnsignedShort => 2,
+      Self::UnsignedInt   => 4,
+      Self::Float         => 4,
+    }
+  }
+}
+
+#[derive(Copy, Clone, serde::Serialize)]
+pub enum Type {
+  SCALAR,
+  VEC2,
+  VEC3,
+  VEC4,
+  MAT2,
+  MAT3,
+  MAT4,
+}
+
+impl Type {
+  pub fn component_count(&self) -> u32 {
+    match self {
+      Self::SCALAR =>  1,
+      Self::VEC2   =>  2,
+      Self::VEC3   =>  3,
+      Self::VEC4   =>  4,
+      Self::MAT2   =>  4,
+      Self::MAT3   =>  9,
+      Self::MAT4   => 16,
+    }
+  }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Accessor {
+  // Next time I modify this, I want to try out:
+  // #[serde(rename_all = "camelCase")]
+  
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  #[serde(rename = "bufferView")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub buffer_view: Option<u32>,
+  
+  #[serde(rename = "byteOffset")]
+  #[serde(skip_serializing_if = "is_default_byte_offset")]
+  pub byte_offset: u32,
+  
+  #[serde(rename = "componentType")]
+  pub component_type: ComponentType,
+  
+  #[serde(skip_serializing_if = "is_default_normalized")]
+  pub normalized: bool,
+  
+  pub count: u32,
+  
+  #[serde(rename = "type")]
+  pub type_: Type,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub max: Vec<f32>,
+  
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub min: Vec<f32>,
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /* pub max: ??,
+   *  pub min: ??,
+   *  pub sparse: ??,
+   *  pub extras: ??,*/
+}
+
+impl Accessor {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self {
+      name: name.into(),
+      buffer_view: None,
+      byte_offset: 0,
+      component_type: ComponentType::Byte,
+      normalized: false,
+      count: 0,
+      type_: Type::SCALAR,
+      min: Vec::new(),
+      max: Vec::new(),
+    }
+  }
+}
+
+fn is_default_byte_offset(value: &u32) -> bool {
+  *value == 0
+}
+
+fn is_default_normalized(value: &bool) -> bool {
+  *value == false
+}
+
+#[derive(Copy, Clone, PartialEq, serde_repr::Serialize_repr)]
+#[repr(u16)]
+pub enum Target {
+  ArrayBuffer = 34962,
+  ElementArrayBuffer = 34963,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct BufferView {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  pub buffer: u32,
+  
+  #[serde(rename = "byteLength")]
+  pub byte_length: u32,
+  
+  #[serde(rename = "byteOffset")]
+  pub byte_offset: u32,
+  
+  #[serde(rename = "byteStride")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub byte_stride: Option<u32>,
+  
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub target: Option<Target>,
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub extras: ??,*/
+}
+
+impl BufferView {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self {
+      name: name.into(),
+      buffer: 0,
+      byte_length: 0,
+      byte_offset: 0,
+      byte_stride: None,
+      target: None,
+    }
+  }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Buffer {
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub name: String,
+  
+  #[serde(rename = "byteLength")]
+  pub byte_length: u32,
+  
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub uri: String,
+  
+  //pub extensions: ??,
+  
+  // In the .gltf spec but will have to wait for now:
+  /*pub extras: ??,*/
+}
+
+impl Buffer {
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Self {
+      name: name.into(),
+      byte_length: 0,
+      uri: String::from(""),
     }
   }
 }
 
-pub struct PackedGeometry {
-  vertex_buffer: u32,
-  triangle_buffer: u32,
+/////////
+// FFI //
+/////////
+
+#[ffi]
+fn init() -> FFIResult<()> {
+  let mut gltf_source = lock(&GLTF_SOURCE)?;
+  *gltf_source = Some(GLTF::new());
+  return Ok(());
 }
 
-/////////////////////////
-// GLTF Data Structure //
-/////////////////////////
+/// When enabled, each later geometry_pack() call puts its data in a new
+/// buffer instead of appending to the shared one
+#[ffi]
+fn set_buffer_per_mesh(enabled: bool) -> FFIResult<()> {
+  // This lock must be saved in a variable before it can be used.
+  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
+  // wrapped in a function
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
+    ErrorCode::NotInitialized)?;
+  
+  gltf_source.buffer_per_mesh = enabled;
+  return Ok(());
+}
 
-#[derive(Clone, serde::Serialize)]
-pub struct Asset {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub copyright: String,
+/// Copy of all model-building state, for checkpoint() and restore()
+struct Checkpoint {
+  gltf_source: Option<GLTF>,
+  geometries: GeometrySlots,
+  packed_geometries: Vec<PackedGeometry>,
+}
+
+/// Snapshots the GLTF (including its BIN data), all geometries, and all
+/// packed geometries. Returns an id for restore(). Snapshots are full copies,
+/// so free them with checkpoint_free() once they are no longer needed
+#[ffi]
+fn checkpoint() -> FFIResult<usize> {
+  let gltf_source = lock(&GLTF_SOURCE)?;
+  let geometries = lock(&GEOMETRIES)?;
+  let packed_geometries = lock(&PACKED_GEOMETRIES)?;
+  let mut checkpoints = lock(&CHECKPOINTS)?;
+  
+  checkpoints.push(Some(Checkpoint {
+    gltf_source: gltf_source.clone(),
+    geometries: geometries.clone(),
+    packed_geometries: packed_geometries.clone(),
+  }));
+  return Ok(checkpoints.len() - 1);
+}
+
+/// Rolls back to a checkpoint. The checkpoint is kept, so it can be restored
+/// again later
+#[ffi]
+fn restore(id: usize) -> FFIResult<()> {
+  let mut gltf_source = lock(&GLTF_SOURCE)?;
+  let mut geometries = lock(&GEOMETRIES)?;
+  let mut packed_geometries = lock(&PACKED_GEOMETRIES)?;
+  let checkpoints = lock(&CHECKPOINTS)?;
+  let checkpoint = checkpoints.get(id).and_then(Option::as_ref).ok_or(
+    ErrorCode::HandleOutOfBounds)?;
   
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub generator: String,
+  *gltf_source = checkpoint.gltf_source.clone();
+  *geometries = checkpoint.geometries.clone();
+  *packed_geometries = checkpoint.packed_geometries.clone();
+  return Ok(());
+}
+
+/// Drops a checkpoint's snapshot. Its id then gives an error, and other ids
+/// stay valid
+#[ffi]
+fn checkpoint_free(id: usize) -> FFIResult<()> {
+  let mut checkpoints = lock(&CHECKPOINTS)?;
+  let slot = checkpoints.get_mut(id).filter(|slot| slot.is_some()).ok_or(
+    ErrorCode::HandleOutOfBounds)?;
   
-  // Don't skip if empty...this field is mandatory per GLTF spec!
-  pub version: String,
+  *slot = None;
+  return Ok(());
+}
+
+/// Sets the most vertices a geometry may reach through primitive builders.
+/// Defaults to 4 million
+#[ffi]
+fn set_max_vertices(max: u32) -> FFIResult<()> {
+  *lock(&MAX_VERTICES)? = max;
+  return Ok(());
+}
+
+#[ffi]
+fn new_material(r: f64, g: f64, b: f64, a: f64, metallicity: f64,
+roughness: f64) -> FFIResult<usize> {
+  let name = get_string_transport(0)?;
   
-  #[serde(skip_serializing_if = "String::is_empty")]
-  #[serde(rename = "minVersion")]
-  pub min_version: String,
+  // This lock must be saved in a variable before it can be used.
+  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
+  // wrapped in a function
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
+    ErrorCode::NotInitialized)?;
   
-  // pub extensions: ??,
+  let handle = gltf_source.materials.len();
+  gltf_source.materials.push(Material::new(name));
+  gltf_source.materials[handle].pbr_metallic_roughness = PBRMetallicRoughness {
+    metallic_factor: metallicity,
+    roughness_factor: roughness,
+    base_color_factor: Color4 { r, g, b, a },
+  };
   
-  // In the .gltf spec, but will have to wait for later
-  //pub extra: ??,
+  return Ok(handle);
 }
 
-impl Asset {
-  pub fn new() -> Self {
-    Self {
-      copyright: String::from(""),
-      generator: String::from("emg v0.1.0"),
-      version: String::from("2.0"),
-      min_version: String::from("2.0"),
+#[ffi]
+fn add_node_to_scene(scene: usize) -> FFIResult<usize> {
+  // This lock must be saved in a variable before it can be used.
+  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
+  // wrapped in a function
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
+    ErrorCode::NotInitialized)?;
+  
+  if scene >= gltf_source.scenes.len() {
+    return Err(ErrorCode::HandleOutOfBounds);
+  }
+  
+  gltf_source.new_root_node(scene as u32, "Fortress Wall Battlement");
+  return Ok(gltf_source.nodes.len() - 1);
+}
+
+#[ffi]
+fn add_mesh_to_node(node: usize) -> FFIResult<usize> {
+  let name = get_string_transport(0)?;
+  
+  // This lock must be saved in a variable before it can be used.
+  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
+  // wrapped in a function
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
+    ErrorCode::NotInitialized)?;
+    
+    if node >= gltf_source.nodes.len() {
+      return Err(ErrorCode::HandleOutOfBounds);
+    }
+    
+    gltf_source.new_mesh(node as u32, name);
+    return Ok(gltf_source.nodes.len() - 1);
+}
+
+#[ffi]
+fn add_primitive_to_mesh(mesh: usize, packed_geometry: usize, material: usize)
+-> FFIResult<usize> {
+  // This lock must be saved in a variable before it can be used.
+  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
+  // wrapped in a function
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
+    ErrorCode::NotInitialized)?;
+  
+  if mesh >= gltf_source.meshes.len() {
+    return Err(ErrorCode::HandleOutOfBounds);
+  }
+  if material >= gltf_source.materials.len() {
+    return Err(ErrorCode::HandleOutOfBounds);
+  }
+  
+  let packed_geometries = lock(&PACKED_GEOMETRIES)?;
+  if packed_geometry >= packed_geometries.len() {
+    return Err(ErrorCode::HandleOutOfBounds);
+  }
+  
+  let packed = &packed_geometries[packed_geometry];
+  let handle = gltf_source.meshes[mesh].primitives.len();
+  
+  // Triangles and lines need separate primitives, since a primitive only has
+  // one mode. The returned handle is for the first primitive added
+  if let Some(triangle_buffer) = packed.triangle_buffer {
+    let mut prim = MeshPrimitive::new();
+    prim.attributes.position = Some(packed.vertex_buffer);
+    prim.attributes.normal = packed.normal_buffer;
+    prim.attributes.texcoord_0 = packed.uv_buffer;
+    prim.attributes.color_0 = packed.color_buffer;
+    prim.indices = Some(triangle_buffer);
+    prim.material = Some(material as u32);
+    gltf_source.meshes[mesh].primitives.push(prim);
+  }
+  
+  if let Some(edge_buffer) = packed.edge_buffer {
+    let mut prim = MeshPrimitive::new();
+    prim.attributes.position = Some(packed.vertex_buffer);
+    prim.attributes.color_0 = packed.color_buffer;
+    prim.indices = Some(edge_buffer);
+    prim.material = Some(material as u32);
+    prim.mode = Mode::Lines;
+    gltf_source.meshes[mesh].primitives.push(prim);
+  }
+  
+  // A node has one origin, so all primitives in a mesh should share a pivot.
+  // The translation is set rather than added, so adding several primitives
+  // with the same pivot is fine
+  if let Some(pivot) = packed.pivot {
+    for node in &mut gltf_source.nodes {
+      if node.mesh == Some(mesh as u32) {
+        node.t = Translation { x: pivot.x, y: pivot.y, z: pivot.z };
+      }
     }
   }
+  
+  return Ok(handle);
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct GLTF {
-  // Don't skip if empty...this field is mandatory per GLTF spec!
-  pub asset: Asset,
+#[ffi]
+fn new_geometry_cube() -> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  geometries.push(Geometry::cube());
+  return Ok(geometries.len() - 1);
+}
+
+/// Drops a geometry's data. Its handle gives HandleOutOfBounds afterward, and
+/// is not reused
+#[ffi]
+fn geometry_free(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub scene: Option<u32>,
+  geometries.free(handle);
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub scenes: Vec<Scene>,
+  Ok(())
+}
+
+/// Copies a geometry, including its selection and vertex attributes, into a
+/// new handle
+#[ffi]
+fn geometry_clone(handle: usize) -> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub nodes: Vec<Node>,
+  let copy = geometries[handle].clone();
+  geometries.push(copy);
+  return Ok(geometries.len() - 1);
+}
+
+#[ffi]
+fn new_geometry() -> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  geometries.push(Geometry::new());
+  return Ok(geometries.len() - 1);
+}
+
+/// Copies `item` onto each triangle of `base`, as a new geometry. Returns the
+/// new geometry's handle
+#[ffi]
+fn geometry_scatter(base: usize, item: usize, align_to_normal: bool)
+-> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(base) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(item) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub materials: Vec<Material>,
+  let result = geometries[base].scatter(&geometries[item],
+    align_to_normal)?;
+  geometries.push(result);
+  return Ok(geometries.len() - 1);
+}
+
+/// Parses `size` bytes of OBJ text from the data transport into a new
+/// geometry, and returns its handle
+#[ffi]
+fn import_obj(size: usize) -> FFIResult<usize> {
+  let data_transport = lock(&DATA_TRANSPORT)?;
+  if size > data_transport.len() { return Err(ErrorCode::SizeOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub meshes: Vec<Mesh>,
+  let text = std::str::from_utf8(&data_transport[..size]).map_err(|_| {
+    ErrorCode::UnicodeError
+  })?;
+  let geometry = Geometry::from_obj(text)?;
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub accessors: Vec<Accessor>,
+  let mut geometries = lock(&GEOMETRIES)?;
+  geometries.push(geometry);
+  return Ok(geometries.len() - 1);
+}
+
+#[ffi]
+fn geometry_clear(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "bufferViews")]
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub buffer_views: Vec<BufferView>,
+  geometries[handle].clear();
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub buffers: Vec<Buffer>,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_add_torus_knot(handle: usize, p: u32, q: u32, segments: u32,
+tube_radius: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // TODO Not sure about the memory use effects of putting all GLB BIN data
-  // into one vector during model construction. Look into using a
-  // Vec<Vec<u8>> or similar when I have a suitable test setup
-  #[serde(skip_serializing)]
-  pub glb_bin: Vec<u8>,
+  geometries[handle].add_torus_knot(p, q, segments, tube_radius)
+}
+
+#[ffi]
+fn geometry_add_stairs(handle: usize, steps: u32, width: f64, rise: f64,
+run: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec, but will have to wait for later
-  /*pub animations: ??
-   *  pub asset: ??
-   *  pub extensionsUsed: ??
-   *  pub extensionsRequired: ??
-   *  pub cameras: ??
-   *  pub images: ??
-   *  pub samplers: ??
-   *  pub skins: ??
-   *  pub textures: ??
-   *  pub extensions: ??
-   *  pub extras: ??*/
+  geometries[handle].add_stairs(steps, width, rise, run)
+}
+
+#[ffi]
+fn geometry_add_frustum(handle: usize, segments: u32, bottom_radius: f64,
+top_radius: f64, unit: bool) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].add_frustum(segments, bottom_radius, top_radius,
+    unit)
+}
+
+/// Text is read as `length` bytes of UTF-8 from the data transport
+#[ffi]
+fn geometry_add_text(handle: usize, length: usize, size: f64, depth: f64)
+-> FFIResult<()> {
+  let data_transport = lock(&DATA_TRANSPORT)?;
+  if length > data_transport.len() { return Err(ErrorCode::SizeOutOfBounds) };
+  
+  let text = std::str::from_utf8(&data_transport[..length]).map_err(|_| {
+    ErrorCode::UnicodeError
+  })?;
+  
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].add_text(text, size, depth)
 }
 
-impl GLTF {
-  pub fn new() -> Self {
-    let scene = Scene::new("A name for a scene");
-    
-    Self {
-      asset: Asset::new(),
-      nodes: Vec::new(),
-      materials: Vec::new(),
-      scene: Some(0),
-      scenes: vec![scene],
-      meshes: Vec::new(),
-      accessors: Vec::new(),
-      buffer_views: Vec::new(),
-      buffers: vec!(Buffer::new("")),
-      glb_bin: Vec::new(),
-    }
-  }
+#[ffi]
+fn geometry_add_grid(handle: usize, x_divisions: u32, y_divisions: u32,
+unit: bool) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  pub fn append_to_glb_bin(&mut self, buffer: impl IntoIterator,
-  type_: Type, component_type: ComponentType) {
-    let mut bytes = 0;
-    for value in buffer.into_iter() {
-      let sliced = unsafe { any_as_u8_slice(&value) };
-      self.glb_bin.extend_from_slice(sliced);
-      bytes += sliced.len() as u32;
-    }
-    self.buffers[0].byte_length += bytes;
-    
-    let mut buffer_view = BufferView::new("");
-    buffer_view.buffer = 0;
-    buffer_view.byte_length = bytes;
-    buffer_view.byte_offset = (self.glb_bin.len() as u32) - bytes;
-    self.buffer_views.push(buffer_view);
-    
-    let mut accessor = Accessor::new("");
-    accessor.buffer_view = Some((self.buffer_views.len() - 1) as u32);
-    accessor.type_ = type_;
-    accessor.component_type = component_type;
-    accessor.count = bytes/type_.component_count()/component_type.byte_count();
-    self.accessors.push(accessor);
-  }
+  geometries[handle].add_grid(x_divisions, y_divisions, unit)
+}
+
+#[ffi]
+fn geometry_add_torus(handle: usize, major_segments: u32, minor_segments: u32,
+minor_radius: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  /// Creates a new node and adds it to the specified scene. If unsure, use
-  /// scene 0
-  pub fn new_root_node<S: Into<String>>(&mut self, scene: u32, name: S) ->
-  *mut Node {
-    let index = self.nodes.len() as u32;
-    self.scenes[scene as usize].nodes.push(index);
-    self.nodes.push(Node::new(name));
-    self.nodes.last_mut().unwrap()
-  }
+  geometries[handle].add_torus(major_segments, minor_segments, minor_radius)
+}
+
+#[ffi]
+fn geometry_add_cone(handle: usize, segments: u32, bottom_radius: f64,
+top_radius: f64, unit: bool) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  /// Creates a new node and adds it to the specified node
-  pub fn new_node<S: Into<String>>(&mut self, node: u32, name: S) -> &mut Node {
-    let index = self.nodes.len() as u32;
-    self.nodes[node as usize].children.push(index);
-    self.nodes.push(Node::new(name));
-    self.nodes.last_mut().unwrap()
-  }
+  geometries[handle].add_cone(segments, bottom_radius, top_radius, unit)
+}
+
+#[ffi]
+fn geometry_add_cylinder_open(handle: usize, segments: u32, unit: bool)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  /// Creates a new mesh and adds it to the specified node
-  pub fn new_mesh<S: Into<String>>(&mut self, node: u32, name: S) -> &mut Mesh {
-  let index = self.meshes.len() as u32;
-    self.nodes[node as usize].mesh = Some(index);
-    self.meshes.push(Mesh::new(name));
-    self.meshes.last_mut().unwrap()
-  }
+  geometries[handle].add_cylinder_open(segments, unit)
+}
+
+#[ffi]
+fn geometry_add_sphere(handle: usize, rings: u32, segments: u32)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  pub fn new_material<S: Into<String>>(&mut self, name: S) -> &mut Material {
-    self.materials.push(Material::new(name));
-    
-    // .unwrap() here doesn't unwrap .material, but instead unwraps the result
-    // of calling .as_mut(), and is permissible because .material is guaranteed
-    // to have a value after the previous line
-    self.materials.last_mut().unwrap()
-  }
+  geometries[handle].add_sphere(rings, segments)
 }
 
-// WARNING: Do not edit!
-//
-// Found this function here:
-// https://stackoverflow.com/questions/28127165/how-to-convert-struct-to-u8
-//
-// Getting something into raw bytes in Rust is absurdly overcomplicated. Code
-// that does this is densely packed with subtle dangers, hidden complications,
-// and unpleasant surprises. Do not attempt to edit it.
-unsafe fn any_as_u8_slice<T: Sized>(p: &T) -> &[u8] {
-  ::core::slice::from_raw_parts(
-    (p as *const T) as *const u8,
-    ::core::mem::size_of::<T>(),
-  )
+#[ffi]
+fn geometry_add_sphere_section(handle: usize, segments: u32, rings: u32,
+start_angle: f64, end_angle: f64, cap: bool) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].add_sphere_section(segments, rings, start_angle,
+    end_angle, cap)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct Scene {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
+#[ffi]
+fn geometry_join(dst: usize, src: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(dst) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(src) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub nodes: Vec<u32>,
+  // Cloned so that joining a geometry to itself does not need two borrows
+  let other = geometries[src].clone();
+  geometries[dst].join(&other);
   
-  //pub extensions: Vec<??>,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_append(dst: usize, src: usize, x: f64, y: f64, z: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(dst) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(src) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but not currently used:
-  //pub extras: Vec<A JSON-serializable struct>,
+  // Cloned so that appending a geometry to itself does not need two borrows
+  let other = geometries[src].clone();
+  geometries[dst].append(&other, V3::new(x, y, z));
+  
+  Ok(())
 }
 
-impl Scene {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self { name: name.into(), nodes: Vec::new() }
-  }
+#[ffi]
+fn geometry_union_convex(dst: usize, src: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(dst) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(src) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  let other = geometries[src].clone();
+  geometries[dst].union_convex(&other)
 }
 
-#[derive(Copy, Clone, PartialEq)]
-#[derive(serde_tuple::Serialize_tuple)]
-pub struct Translation {
-  pub x: f64,
-  pub y: f64,
-  pub z: f64,
+#[ffi]
+fn geometry_join_keep(dst: usize, src: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(dst) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(src) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  // Cloned so that joining a geometry to itself does not need two borrows
+  let other = geometries[src].clone();
+  geometries[dst].join_keep_selection(&other);
+  
+  Ok(())
 }
 
-impl Translation {
-  pub fn new() -> Self { Self { x: 0.0, y: 0.0, z: 0.0 } }
-  pub fn is_default(&self) -> bool { *self == Self::new() }
+#[ffi]
+fn geometry_translate(handle: usize, x: f64, y: f64, z: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].t(x, y, z);
+  
+  Ok(())
 }
 
-#[derive(Copy, Clone, PartialEq)]
-#[derive(serde_tuple::Serialize_tuple)]
-pub struct Rotation {
-  pub x: f64,
-  pub y: f64,
-  pub z: f64,
-  pub w: f64,
+#[ffi]
+fn geometry_scale(handle: usize, x: f64, y: f64, z: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].s(x, y, z);
+  
+  Ok(())
 }
 
-impl Rotation {
-  pub fn new() -> Self { Self { x: 0.0, y: 0.0, z: 0.0, w: 1.0 } }
-  pub fn is_default(&self) -> bool { *self == Self::new() }
+#[ffi]
+fn geometry_rotate_euler_about(handle: usize, px: f64, py: f64, pz: f64,
+x: f64, y: f64, z: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].rotate_euler_about(V3::new(px, py, pz), x, y, z)
 }
 
-#[derive(Copy, Clone, PartialEq)]
-#[derive(serde_tuple::Serialize_tuple)]
-pub struct Scale {
-  pub x: f64,
-  pub y: f64,
-  pub z: f64,
+#[ffi]
+fn geometry_scale_about(handle: usize, px: f64, py: f64, pz: f64, x: f64,
+y: f64, z: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].scale_about(V3::new(px, py, pz), x, y, z)
 }
 
-impl Scale {
-  pub fn new() -> Self { Self { x: 1.0, y: 1.0, z: 1.0 } }
-  pub fn is_default(&self) -> bool { *self == Self::new() }
+#[ffi]
+#[allow(clippy::too_many_arguments)]
+fn geometry_transform_matrix(handle: usize, m0: f64, m1: f64, m2: f64,
+m3: f64, m4: f64, m5: f64, m6: f64, m7: f64, m8: f64, m9: f64, m10: f64,
+m11: f64, m12: f64, m13: f64, m14: f64, m15: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  return geometries[handle].transform_matrix([m0, m1, m2, m3, m4, m5, m6, m7,
+    m8, m9, m10, m11, m12, m13, m14, m15]);
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct Node {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
+#[ffi]
+fn geometry_array_radial(handle: usize, count: u32, ax: f64, ay: f64, az: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub mesh: Option<u32>,
+  geometries[handle].array_radial(count, V3::new(ax, ay, az))
+}
+
+/// Samples are read as little-endian f64s from the data transport
+#[ffi]
+fn geometry_taper_profile(handle: usize, axis: u32, sample_count: usize,
+axis_min: f64, axis_max: f64) -> FFIResult<()> {
+  let samples = get_data_transport_f64(sample_count)?;
   
-  #[serde(rename = "translation")]
-  #[serde(skip_serializing_if = "Translation::is_default")]
-  pub t: Translation,
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  #[serde(rename = "rotation")]
-  #[serde(skip_serializing_if = "Rotation::is_default")]
-  pub r: Rotation,
+  geometries[handle].taper_profile(axis as u8, &samples, axis_min, axis_max)
+}
+
+#[ffi]
+fn geometry_is_selected(handle: usize, vtx: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if vtx >= geometries[handle].vertices.len() {
+    return Err(ErrorCode::VtxOutOfBounds);
+  }
   
-  #[serde(rename = "scale")]
-  #[serde(skip_serializing_if = "Scale::is_default")]
-  pub s: Scale,
+  return Ok(geometries[handle].is_selected(vtx as u32) as usize);
+}
+
+#[ffi]
+fn geometry_merge(handle: usize, x: f64, y: f64, z: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub children: Vec<u32>,
+  geometries[handle].merge(V3::new(x, y, z));
   
-  //pub mesh: ??,
-  //pub extensions: ??,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_merge_center(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub camera: ??,
-   *  pub skin: ??,
-   *  pub matrix: ??,
-   *  pub weights: ??,
-   *  pub extras: ??,*/
+  geometries[handle].merge_center();
+  
+  Ok(())
 }
 
-impl Node {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self {
-      name: name.into(),
-      mesh: None,
-      t: Translation::new(),
-      r: Rotation::new(),
-      s: Scale::new(),
-      children: Vec::new(),
-    }
-  }
+#[ffi]
+fn geometry_weld(handle: usize, epsilon: f64) -> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].weld(epsilon)
 }
 
-#[derive(Copy, Clone, PartialEq, serde::Serialize)]
-pub enum AlphaMode {
-  OPAQUE,
-  MASK,
-  BLEND,
+#[ffi]
+fn geometry_remove_doubles_respect_normals(handle: usize, threshold: f64,
+max_angle: f64) -> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].remove_doubles_respect_normals(threshold, max_angle)
 }
 
-#[derive(Copy, Clone, PartialEq)]
-#[derive(serde_tuple::Serialize_tuple)]
-pub struct Color4 {
-  pub r: f64,
-  pub g: f64,
-  pub b: f64,
-  pub a: f64,
+#[ffi]
+fn geometry_stitch_boundaries(handle: usize, threshold: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].stitch_boundaries(threshold)
 }
 
-impl Color4 {
-  pub fn new() -> Self { Self { r: 1.0, g: 1.0, b: 1.0, a: 1.0 } }
-  pub fn is_default(&self) -> bool { *self == Self::new() }
+#[ffi]
+fn geometry_merge_average(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].merge_average();
+  
+  Ok(())
 }
 
-#[derive(Copy, Clone, serde::Serialize)]
-pub struct PBRMetallicRoughness {
-  #[serde(rename = "baseColorFactor")]
-  #[serde(skip_serializing_if = "Color4::is_default")]
-  pub base_color_factor: Color4,
+#[ffi]
+fn geometry_collapse_short_edges(handle: usize, min_length: f64)
+-> FFIResult<usize> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "metallicFactor")]
-  #[serde(skip_serializing_if = "is_default_metallic_factor")]
-  pub metallic_factor: f64,
+  return Ok(geometries[handle].collapse_short_edges(min_length));
+}
+
+#[ffi]
+fn geometry_repair(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "roughnessFactor")]
-  #[serde(skip_serializing_if = "is_default_roughness_factor")]
-  pub roughness_factor: f64,
+  geometries[handle].repair();
   
-  //pub extensions: ??,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_get_selected_vtx_count(handle: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub extras: ??,
-   *  pub metallicRoughnessTexture: ??,
-   *  pub baseColorTexture: ??,
-   */
+  return Ok(geometries[handle].selected_vertices().len());
 }
 
-impl PBRMetallicRoughness {
-  pub fn new() -> Self {
-    Self {
-      base_color_factor: Color4::new(),
-      metallic_factor: 1.0,
-      roughness_factor: 1.0,
-    }
-  }
+#[ffi]
+fn geometry_get_selected_tri_count(handle: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  return Ok(geometries[handle].selected_triangles().len());
 }
 
-fn is_default_metallic_factor(value: &f64) -> bool {
-  *value == 1.0
+#[ffi]
+fn geometry_get_nonmanifold_edge_count(handle: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  return Ok(geometries[handle].nonmanifold_edge_count());
 }
 
-fn is_default_roughness_factor(value: &f64) -> bool {
-  *value == 1.0
+#[ffi]
+fn geometry_get_hole_count(handle: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  return Ok(geometries[handle].hole_count());
 }
 
-fn is_default_emissive_factor(value: &[f64; 3]) -> bool {
-  *value == [0.0, 0.0, 0.0]
+#[ffi]
+fn geometry_is_winding_consistent(handle: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  return Ok(geometries[handle].is_winding_consistent() as usize);
 }
 
-fn is_default_alpha_mode(value: &AlphaMode) -> bool {
-  *value == AlphaMode::OPAQUE
+#[ffi]
+fn geometry_get_closed_volume(handle: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries[handle].is_closed() { return Err(ErrorCode::NotClosed) };
+  
+  return Ok(geometries[handle].volume() as f32);
 }
 
-fn is_default_alpha_cutoff(value: &f64) -> bool {
-  *value == 0.5
+#[ffi]
+fn geometry_get_volume_centroid(handle: usize, axis: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
+  if !geometries[handle].is_closed() { return Err(ErrorCode::NotClosed) };
+  
+  return Ok(geometries[handle].volume_centroid()[axis] as f32);
 }
 
-fn is_default_double_sided(value: &bool) -> bool {
-  *value == false
+#[ffi]
+fn geometry_get_selection_min(handle: usize, axis: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
+  
+  let (min, _) = geometries[handle].selection_aabb().ok_or(
+    ErrorCode::EmptySelection)?;
+  return Ok(min[axis] as f32);
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct Material {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
+#[ffi]
+fn geometry_get_selection_max(handle: usize, axis: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  #[serde(rename = "emissiveFactor")]
-  #[serde(skip_serializing_if = "is_default_emissive_factor")]
-  pub emissive_factor: [f64; 3],
+  let (_, max) = geometries[handle].selection_aabb().ok_or(
+    ErrorCode::EmptySelection)?;
+  return Ok(max[axis] as f32);
+}
+
+#[ffi]
+fn geometry_get_selection_centroid(handle: usize, axis: usize)
+-> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  #[serde(rename = "alphaMode")]
-  #[serde(skip_serializing_if = "is_default_alpha_mode")]
-  pub alpha_mode: AlphaMode,
+  let centroid = geometries[handle].selection_centroid().ok_or(
+    ErrorCode::EmptySelection)?;
+  return Ok(centroid[axis] as f32);
+}
+
+#[ffi]
+fn geometry_get_min_triangle_quality(handle: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "alphaCutoff")]
-  #[serde(skip_serializing_if = "is_default_alpha_cutoff")]
-  pub alpha_cutoff: f64,
+  let geometry = &geometries[handle];
+  if geometry.triangles.is_empty() { return Err(ErrorCode::EmptyGeometry) };
   
-  #[serde(rename = "doubleSided")]
-  #[serde(skip_serializing_if = "is_default_double_sided")]
-  pub double_sided: bool,
+  return Ok((0..geometry.triangles.len()).map(|i| {
+    geometry.triangle_quality(i)
+  }).fold(f64::MAX, f64::min) as f32);
+}
+
+#[ffi]
+fn geometry_count_tris_above_area(handle: usize, min_area: f64)
+-> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "pbrMetallicRoughness")]
-  // Not sure how to skip serializing when unused for this one
-  pub pbr_metallic_roughness: PBRMetallicRoughness,
+  let geometry = &geometries[handle];
+  return Ok((0..geometry.triangles.len()).filter(|&i| {
+    geometry.triangle_area(i) > min_area
+  }).count());
+}
+
+#[ffi]
+fn geometry_get_surface_area(handle: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  //pub extensions: ??,
+  let geometry = &geometries[handle];
+  return Ok((0..geometry.triangles.len()).map(|i| {
+    geometry.triangle_area(i)
+  }).sum::<f64>() as f32);
+}
+
+#[ffi]
+fn geometry_get_selected_area(handle: usize) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub extras: ??,
-   *  pub normalTexture: ??,
-   *  pub occlusionTexture: ??,
-   *  pub emissiveTexture: ??,*/
+  let geometry = &geometries[handle];
+  return Ok(geometry.selected_triangles().iter().map(|&i| {
+    geometry.triangle_area(i as usize)
+  }).sum::<f64>() as f32);
 }
 
-impl Material {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self {
-      name: name.into(),
-      emissive_factor: [0.0, 0.0, 0.0],
-      alpha_mode: AlphaMode::OPAQUE,
-      alpha_cutoff: 0.5,
-      double_sided: false,
-      pbr_metallic_roughness: PBRMetallicRoughness::new(),
-    }
-  }
+#[ffi]
+fn geometry_quantize(handle: usize, step: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].quantize(step)
 }
 
-// The fields here are in the spec in section 3.7 - Concepts / Geometry,
-// which took me a while to find
-#[derive(Copy, Clone, serde::Serialize)]
-pub struct Attributes {
-  #[serde(rename = "COLOR_0")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub color_0: Option<u32>,
+#[ffi]
+fn geometry_snap_to_plane(handle: usize, axis: u32, value: f64,
+tolerance: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  #[serde(rename = "JOINTS_0")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub joints_0: Option<u32>,
+  geometries[handle].snap_to_plane(axis as u8, value, tolerance)
+}
+
+#[ffi]
+fn geometry_normalize(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "NORMAL")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub normal: Option<u32>,
+  geometries[handle].normalize();
   
-  #[serde(rename = "POSITION")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub position: Option<u32>,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_compute_normals(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "TANGENT")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub tangent: Option<u32>,
+  geometries[handle].compute_normals(NormalWeighting::Area);
   
-  #[serde(rename = "TEXCOORD_0")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub texcoord_0: Option<u32>,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_split_faces(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "TEXCOORD_1")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub texcoord_1: Option<u32>,
+  geometries[handle].split_faces();
   
-  #[serde(rename = "TEXCOORD_2")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub texcoord_2: Option<u32>,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_compute_flat_normals(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "TEXCOORD_3")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub texcoord_3: Option<u32>,
+  geometries[handle].compute_flat_normals();
   
-  #[serde(rename = "WEIGHTS_0")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub weights_0: Option<u32>,
+  Ok(())
 }
 
-impl Attributes {
-  pub fn new() -> Self {
-    Self {
-      color_0: None,
-      joints_0: None,
-      normal: None,
-      position: None,
-      tangent: None,
-      texcoord_0: None,
-      texcoord_1: None,
-      texcoord_2: None,
-      texcoord_3: None,
-      weights_0: None,
-    }
-  }
+#[ffi]
+fn geometry_compute_normals_with_angle(handle: usize, max_angle: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].compute_normals_with_angle(max_angle);
+  
+  Ok(())
 }
 
-#[derive(Copy, Clone, PartialEq, serde_repr::Serialize_repr)]
-#[repr(u8)]
-pub enum Mode {
-  Points = 0,
-  Lines = 1,
-  LineLoop = 2,
-  LineStrip = 3,
-  Triangles = 4,
-  TriangleStrip = 5,
-  TriangleFan = 6,
+#[ffi]
+fn geometry_set_smooth_group(handle: usize, group: u32) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].set_smooth_group(group);
+  
+  Ok(())
 }
 
-fn is_default_mode(value: &Mode) -> bool {
-  *value == Mode::Triangles
+#[ffi]
+fn geometry_set_crease(handle: usize, weight: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].set_crease(weight)
 }
 
-#[derive(Copy, Clone, serde::Serialize)]
-pub struct MeshPrimitive {
-  pub attributes: Attributes,
+#[ffi]
+fn geometry_get_crease(handle: usize, a: u32, b: u32) -> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  return Ok(geometries[handle].get_crease(a, b)? as f32);
+}
+
+#[ffi]
+fn geometry_compute_normals_weighted(handle: usize, weighting: u32)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub indices: Option<u32>,
+  geometries[handle].compute_normals(weighting.try_into()?);
   
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub material: Option<u32>,
+  Ok(())
+}
+
+#[ffi]
+#[allow(clippy::too_many_arguments)]
+fn geometry_spin(handle: usize, steps: u32, angle: f64, ax: f64, ay: f64,
+az: f64, cx: f64, cy: f64, cz: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "is_default_mode")]
-  pub mode: Mode, // Default is triangles
+  geometries[handle].spin(steps, angle, V3::new(ax, ay, az),
+    V3::new(cx, cy, cz))
+}
+
+#[ffi]
+fn geometry_extrude_path(handle: usize, point_count: usize) -> FFIResult<()> {
+  let coordinates = get_data_transport_f64(point_count.checked_mul(3)
+    .ok_or(ErrorCode::SizeOutOfBounds)?)?;
+  let path: Vec<V3<f64>> = coordinates.as_chunks::<3>().0.iter()
+    .map(|&[x, y, z]| V3::new(x, y, z)).collect();
   
-  //pub extensions: ??,
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub extras: ??,
-   *  pub targets: ??,*/
+  geometries[handle].extrude_path(&path)
 }
 
-impl MeshPrimitive {
-  pub fn new() -> Self {
-    Self {
-      attributes: Attributes::new(),
-      indices: None,
-      material: None,
-      mode: Mode::Triangles,
-    }
-  }
+#[ffi]
+fn geometry_subdivide(handle: usize, iterations: u32) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  /// Set material index
-  pub fn material(&mut self, material: u32) -> &mut Self {
-    self.material = Some(material);
-    self
-  }
+  geometries[handle].subdivide(iterations)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct Mesh {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
+#[ffi]
+fn geometry_smooth(handle: usize, iterations: u32, factor: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // No serialization filter, this is required per spec
-  pub primitives: Vec<MeshPrimitive>,
+  geometries[handle].smooth(iterations, factor)
+}
+
+#[ffi]
+fn geometry_displace_noise(handle: usize, amplitude: f64, frequency: f64,
+seed: u32) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub weights: Vec<f64>,
+  geometries[handle].displace_noise(amplitude, frequency, seed);
   
-  //pub extensions: ??,
+  Ok(())
+}
+
+#[ffi]
+fn geometry_solidify(handle: usize, thickness: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub extras: ??,*/
+  geometries[handle].solidify(thickness)
 }
 
-impl Mesh {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self {
-      name: name.into(),
-      primitives: Vec::new(),
-      weights: Vec::new(),
-    }
-  }
+#[ffi]
+fn geometry_extrude_copy(handle: usize, x: f64, y: f64, z: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  pub fn copy_primitive(&mut self, primitive: MeshPrimitive) ->
-  &mut MeshPrimitive {
-    self.primitives.push(primitive);
-    self.primitives.last_mut().unwrap()
-  }
+  geometries[handle].extrude_copy(V3::new(x, y, z));
+  
+  Ok(())
 }
 
-#[derive(Copy, Clone, PartialEq, serde_repr::Serialize_repr)]
-#[repr(u16)]
-pub enum ComponentType {
-  Byte = 5120,
-  UnsignedByte = 5121,
-  Short = 5122,
-  UnsignedShort = 5123,
-  UnsignedInt = 5125,
-  Float = 5126,
+#[ffi]
+fn geometry_smooth_boundary(handle: usize, iterations: u32) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].smooth_boundary(iterations);
+  
+  Ok(())
 }
 
-impl ComponentType {
-  pub fn byte_count(&self) -> u32 {
-    match self {
-      Self::Byte          => 1,
-      Self::UnsignedByte  => 1,
-      Self::Short         => 2,
-      Self::UnsignedShort => 2,
-      Self::UnsignedInt   => 4,
-      Self::Float         => 4,
-    }
-  }
+#[ffi]
+fn geometry_merge_coplanar(handle: usize, angle_tolerance: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].merge_coplanar(angle_tolerance)
 }
 
-#[derive(Copy, Clone, serde::Serialize)]
-pub enum Type {
-  SCALAR,
-  VEC2,
-  VEC3,
-  VEC4,
-  MAT2,
-  MAT3,
-  MAT4,
+#[ffi]
+fn geometry_thicken_profile(handle: usize, thickness: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].thicken_profile(thickness)
 }
 
-impl Type {
-  pub fn component_count(&self) -> u32 {
-    match self {
-      Self::SCALAR =>  1,
-      Self::VEC2   =>  2,
-      Self::VEC3   =>  3,
-      Self::VEC4   =>  4,
-      Self::MAT2   =>  4,
-      Self::MAT3   =>  9,
-      Self::MAT4   => 16,
-    }
+#[ffi]
+fn geometry_orient_like(handle: usize, reference_handle: usize)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(reference_handle) {
+    return Err(ErrorCode::HandleOutOfBounds);
   }
+  
+  // Cloned because both geometries live in the same Vec
+  let reference = geometries[reference_handle].clone();
+  geometries[handle].orient_like(&reference)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct Accessor {
-  // Next time I modify this, I want to try out:
-  // #[serde(rename_all = "camelCase")]
-  
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
-  
-  #[serde(rename = "bufferView")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub buffer_view: Option<u32>,
-  
-  #[serde(rename = "byteOffset")]
-  #[serde(skip_serializing_if = "is_default_byte_offset")]
-  pub byte_offset: u32,
+#[ffi]
+fn geometry_closest_vtx(handle: usize, x: f64, y: f64, z: f64)
+-> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "componentType")]
-  pub component_type: ComponentType,
+  let vertex = geometries[handle].closest_vertex(V3::new(x, y, z)).ok_or(
+    ErrorCode::EmptyGeometry)?;
   
-  #[serde(skip_serializing_if = "is_default_normalized")]
-  pub normalized: bool,
+  return Ok(vertex as usize);
+}
+
+/// Returns 0xffffffff if the edge is open
+#[ffi]
+fn geometry_get_tri_neighbor(handle: usize, tri: u32, edge: usize)
+-> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  pub count: u32,
+  let neighbor = geometries[handle].triangle_neighbor(tri, edge)?;
   
-  #[serde(rename = "type")]
-  pub type_: Type,
+  return Ok(neighbor.map_or(0xffffffff, |i| i as usize));
+}
+
+#[ffi]
+fn geometry_transfer_selection(src: usize, dst: usize, threshold: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(src) { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(dst) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub max: Vec<f32>,
+  // Cloned so that transferring within one geometry does not need two borrows
+  let source = geometries[src].clone();
+  geometries[dst].transfer_selection(&source, threshold)
+}
+
+#[ffi]
+fn geometry_set_normals_direction(handle: usize, x: f64, y: f64, z: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub min: Vec<f32>,
+  geometries[handle].set_normals_direction(V3::new(x, y, z))
+}
+
+#[ffi]
+fn geometry_add_line(handle: usize, ax: f64, ay: f64, az: f64, bx: f64,
+by: f64, bz: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  //pub extensions: ??,
+  geometries[handle].add_line(V3::new(ax, ay, az), V3::new(bx, by, bz));
   
-  // In the .gltf spec but will have to wait for now:
-  /* pub max: ??,
-   *  pub min: ??,
-   *  pub sparse: ??,
-   *  pub extras: ??,*/
+  Ok(())
 }
 
-impl Accessor {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self {
-      name: name.into(),
-      buffer_view: None,
-      byte_offset: 0,
-      component_type: ComponentType::Byte,
-      normalized: false,
-      count: 0,
-      type_: Type::SCALAR,
-      min: Vec::new(),
-      max: Vec::new(),
-    }
+#[ffi]
+fn geometry_get_principal_axis(handle: usize, which: usize, component: usize)
+-> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if which >= 3 || component >= 3 {
+    return Err(ErrorCode::ParameterOutOfRange);
   }
+  
+  return Ok(geometries[handle].principal_axes()[which][component] as f32);
 }
 
-fn is_default_byte_offset(value: &u32) -> bool {
-  *value == 0
+/// Control points are read as little-endian f64 x, y, z triples from the data
+/// transport
+#[ffi]
+fn geometry_lattice_deform(handle: usize, dx: u32, dy: u32, dz: u32)
+-> FFIResult<()> {
+  // Checked because absurd divisions could otherwise overflow usize
+  let count = [dx, dy, dz].iter().try_fold(3usize, |count, &d| {
+    count.checked_mul((d as usize).checked_add(1)?)
+  }).ok_or(ErrorCode::SizeOutOfBounds)?;
+  let coordinates = get_data_transport_f64(count)?;
+  let control_points: Vec<V3<f64>> = coordinates.as_chunks::<3>().0.iter()
+    .map(|&[x, y, z]| V3::new(x, y, z)).collect();
+  
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].lattice_deform([dx, dy, dz], &control_points)
 }
 
-fn is_default_normalized(value: &bool) -> bool {
-  *value == false
+#[ffi]
+fn geometry_select_similar_area(handle: usize, reference_tri: u32,
+tolerance: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].select_similar_area(reference_tri, tolerance)
 }
 
-#[derive(Copy, Clone, PartialEq, serde_repr::Serialize_repr)]
-#[repr(u16)]
-pub enum Target {
-  ArrayBuffer = 34962,
-  ElementArrayBuffer = 34963,
+#[ffi]
+#[allow(clippy::too_many_arguments)]
+fn geometry_uv_project_camera(handle: usize, px: f64, py: f64, pz: f64,
+dx: f64, dy: f64, dz: f64, fov: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].uv_project_camera(V3::new(px, py, pz),
+    V3::new(dx, dy, dz), fov)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct BufferView {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
+#[ffi]
+fn geometry_generate_uv_planar(handle: usize, axis: u32) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  pub buffer: u32,
+  geometries[handle].generate_uv_planar(axis as u8)
+}
+
+#[ffi]
+fn geometry_generate_uv_box(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "byteLength")]
-  pub byte_length: u32,
+  geometries[handle].generate_uv_box();
   
-  #[serde(rename = "byteOffset")]
-  pub byte_offset: u32,
+  Ok(())
+}
+
+/// The data transport holds little-endian u32s: for each loop, its length
+/// followed by its vertex indices
+#[ffi]
+fn geometry_loft(handle: usize, loop_count: usize) -> FFIResult<()> {
+  let data = get_data_transport_u32()?;
+  
+  let mut loops = Vec::new();
+  let mut position = 0;
+  for _ in 0..loop_count {
+    let length = *data.get(position).ok_or(ErrorCode::SizeOutOfBounds)?
+      as usize;
+    let end = position.checked_add(1).and_then(|p| p.checked_add(length))
+      .ok_or(ErrorCode::SizeOutOfBounds)?;
+    let indices = data.get(position + 1..end).ok_or(
+      ErrorCode::SizeOutOfBounds)?;
+    
+    loops.push(indices.to_vec());
+    position = end;
+  }
   
-  #[serde(rename = "byteStride")]
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub byte_stride: Option<u32>,
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "Option::is_none")]
-  pub target: Option<Target>,
+  geometries[handle].loft(&loops)
+}
+
+#[ffi]
+fn geometry_get_normal(handle: usize, vtx: u32, axis: usize)
+-> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if axis >= 3 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  //pub extensions: ??,
+  return Ok(geometries[handle].get_normal(vtx)?[axis] as f32);
+}
+
+#[ffi]
+fn geometry_get_uv(handle: usize, vtx: u32, component: usize)
+-> FFIResult<f32> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  if component >= 2 { return Err(ErrorCode::ParameterOutOfRange) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub extras: ??,*/
+  return Ok(geometries[handle].get_uv(vtx)?[component] as f32);
 }
 
-impl BufferView {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self {
-      name: name.into(),
-      buffer: 0,
-      byte_length: 0,
-      byte_offset: 0,
-      byte_stride: None,
-      target: None,
-    }
-  }
+#[ffi]
+fn geometry_set_uv(handle: usize, vtx: u32, u: f64, v: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].set_uv(vtx, u, v)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct Buffer {
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub name: String,
+#[ffi]
+fn geometry_set_vtx_color(handle: usize, vtx: u32, r: f64, g: f64, b: f64,
+a: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(rename = "byteLength")]
-  pub byte_length: u32,
+  geometries[handle].set_vtx_color(vtx, [r, g, b, a].map(|x| x as f32))
+}
+
+#[ffi]
+fn geometry_transform_uv(handle: usize, a: f64, b: f64, c: f64, d: f64,
+tx: f64, ty: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  #[serde(skip_serializing_if = "String::is_empty")]
-  pub uri: String,
+  geometries[handle].transform_uv(a, b, c, d, tx, ty)
+}
+
+#[ffi]
+fn geometry_flip_uv(handle: usize, u: bool, v: bool) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  //pub extensions: ??,
+  geometries[handle].flip_uv(u, v)
+}
+
+#[ffi]
+fn geometry_scale_uv(handle: usize, su: f64, sv: f64, pu: f64, pv: f64)
+-> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // In the .gltf spec but will have to wait for now:
-  /*pub extras: ??,*/
+  geometries[handle].scale_uv(su, sv, pu, pv)
 }
 
-impl Buffer {
-  pub fn new<S: Into<String>>(name: S) -> Self {
-    Self {
-      name: name.into(),
-      byte_length: 0,
-      uri: String::from(""),
-    }
-  }
+#[ffi]
+fn geometry_uv_pack(handle: usize, margin: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].uv_pack(margin)
 }
 
-/////////
-// FFI //
-/////////
+#[ffi]
+fn geometry_uv_unwrap(handle: usize, angle_threshold: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].uv_unwrap(angle_threshold)
+}
 
 #[ffi]
-fn init() -> FFIResult<()> {
-  let mut gltf_source = lock(&GLTF_SOURCE)?;
-  *gltf_source = Some(GLTF::new());
-  return Ok(());
+fn geometry_retriangulate_shortest(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].retriangulate_shortest_diagonal();
+  
+  Ok(())
 }
 
 #[ffi]
-fn new_material(r: f64, g: f64, b: f64, a: f64, metallicity: f64,
-roughness: f64) -> FFIResult<usize> {
-  let name = get_string_transport(0)?;
+fn geometry_set_selection_mode(handle: usize, mode: u32) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // This lock must be saved in a variable before it can be used.
-  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
-  // wrapped in a function
-  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
-  let gltf_source = gltf_source_option.as_mut().ok_or(
-    ErrorCode::NotInitialized)?;
+  geometries[handle].set_selection_mode(mode.try_into()?);
   
-  let handle = gltf_source.materials.len();
-  gltf_source.materials.push(Material::new(name));
-  gltf_source.materials[handle].pbr_metallic_roughness = PBRMetallicRoughness {
-    metallic_factor: metallicity,
-    roughness_factor: roughness,
-    base_color_factor: Color4 { r, g, b, a },
-  };
+  Ok(())
+}
+
+#[ffi]
+fn geometry_get_selection_mode(handle: usize) -> FFIResult<usize> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  return Ok(handle);
+  return Ok(geometries[handle].selection_type as usize);
 }
 
 #[ffi]
-fn add_node_to_scene(scene: usize) -> FFIResult<usize> {
-  // This lock must be saved in a variable before it can be used.
-  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
-  // wrapped in a function
-  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
-  let gltf_source = gltf_source_option.as_mut().ok_or(
-    ErrorCode::NotInitialized)?;
+fn geometry_set_pivot(handle: usize, x: f64, y: f64, z: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  if scene >= gltf_source.scenes.len() {
-    return Err(ErrorCode::HandleOutOfBounds);
-  }
+  geometries[handle].pivot = Some(V3::new(x, y, z));
   
-  gltf_source.new_root_node(scene as u32, "Fortress Wall Battlement");
-  return Ok(gltf_source.nodes.len() - 1);
+  Ok(())
 }
 
 #[ffi]
-fn add_mesh_to_node(node: usize) -> FFIResult<usize> {
-  let name = get_string_transport(0)?;
+fn geometry_select_boundary(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  // This lock must be saved in a variable before it can be used.
-  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
-  // wrapped in a function
-  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
-  let gltf_source = gltf_source_option.as_mut().ok_or(
-    ErrorCode::NotInitialized)?;
-    
-    if node >= gltf_source.nodes.len() {
-      return Err(ErrorCode::HandleOutOfBounds);
-    }
-    
-    gltf_source.new_mesh(node as u32, name);
-    return Ok(gltf_source.nodes.len() - 1);
+  geometries[handle].select_boundary();
+  
+  Ok(())
 }
 
 #[ffi]
-fn add_primitive_to_mesh(mesh: usize, packed_geometry: usize, material: usize)
--> FFIResult<usize> {
-  // This lock must be saved in a variable before it can be used.
-  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
-  // wrapped in a function
-  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
-  let gltf_source = gltf_source_option.as_mut().ok_or(
-    ErrorCode::NotInitialized)?;
+fn geometry_select_grow(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  if mesh >= gltf_source.meshes.len() {
-    return Err(ErrorCode::HandleOutOfBounds);
-  }
-  if material >= gltf_source.materials.len() {
-    return Err(ErrorCode::HandleOutOfBounds);
-  }
+  geometries[handle].select_grow();
   
-  let packed_geometries = lock(&PACKED_GEOMETRIES)?;
-  if packed_geometry >= packed_geometries.len() {
-    return Err(ErrorCode::HandleOutOfBounds);
-  }
+  Ok(())
+}
+
+#[ffi]
+fn geometry_select_shrink(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  let mut prim = MeshPrimitive::new();
-  prim.attributes.position = Some(packed_geometries[packed_geometry]
-    .vertex_buffer);
-  prim.indices = Some(packed_geometries[packed_geometry].triangle_buffer);
-  prim.material = Some(material as u32);
-  gltf_source.meshes[mesh].primitives.push(prim);
-  return Ok(gltf_source.meshes[mesh].primitives.len() - 1);
+  geometries[handle].select_shrink();
+  
+  Ok(())
 }
 
 #[ffi]
-fn new_geometry_cube() -> FFIResult<usize> {
+fn geometry_select_by_normal(handle: usize, dx: f64, dy: f64, dz: f64,
+max_angle: f64) -> FFIResult<()> {
   let mut geometries = lock(&GEOMETRIES)?;
-  geometries.push(Geometry::cube());
-  return Ok(geometries.len() - 1);
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].select_by_normal(V3::new(dx, dy, dz), max_angle)
 }
 
 #[ffi]
-fn geometry_translate(handle: usize, x: f64, y: f64, z: f64) -> FFIResult<()> {
+fn geometry_select_linked(handle: usize) -> FFIResult<()> {
   let mut geometries = lock(&GEOMETRIES)?;
-  if handle >= geometries.len() { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  geometries[handle].t(x, y, z);
+  geometries[handle].select_linked();
   
   Ok(())
 }
 
 #[ffi]
-fn geometry_scale(handle: usize, x: f64, y: f64, z: f64) -> FFIResult<()> {
+fn geometry_select_invert(handle: usize) -> FFIResult<()> {
   let mut geometries = lock(&GEOMETRIES)?;
-  if handle >= geometries.len() { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
-  geometries[handle].s(x, y, z);
+  geometries[handle].select_invert();
   
   Ok(())
 }
 
+/// Vertex indices are read as little-endian u32s from the data transport
+#[ffi]
+fn geometry_select_vertex_list(handle: usize, count: usize) -> FFIResult<()> {
+  let data = get_data_transport_u32()?;
+  let vertices = data.get(..count).ok_or(ErrorCode::SizeOutOfBounds)?;
+  
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].select_vertex_list(vertices)
+}
+
+#[ffi]
+fn geometry_select_creases(handle: usize, min_angle: f64) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].select_creases(min_angle)
+}
+
 #[ffi]
 fn geometry_select_triangles(handle: usize, x1: f64, y1: f64, z1: f64, x2: f64,
 y2: f64, z2: f64) -> FFIResult<()> {
   let mut geometries = lock(&GEOMETRIES)?;
-  if handle >= geometries.len() { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
   geometries[handle].select_triangles(V3::new(x1, y1, z1), V3::new(x2, y2, z2));
   
@@ -1300,13 +7138,23 @@ y2: f64, z2: f64) -> FFIResult<()> {
 #[ffi]
 fn geometry_delete_triangles(handle: usize) -> FFIResult<()> {
   let mut geometries = lock(&GEOMETRIES)?;
-  if handle >= geometries.len() { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   
   geometries[handle].delete_triangles();
   
   Ok(())
 }
 
+#[ffi]
+fn geometry_delete_stray_vertices(handle: usize) -> FFIResult<()> {
+  let mut geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  geometries[handle].delete_stray_vertices();
+  
+  Ok(())
+}
+
 #[ffi]
 fn geometry_pack(handle: usize) -> FFIResult<usize> {
   // This lock must be saved in a variable before it can be used.
@@ -1317,13 +7165,155 @@ fn geometry_pack(handle: usize) -> FFIResult<usize> {
     ErrorCode::NotInitialized)?;
   
   let geometries = lock(&GEOMETRIES)?;
-  if handle >= geometries.len() { return Err(ErrorCode::HandleOutOfBounds) };
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
   let mut packed_geometries = lock(&PACKED_GEOMETRIES)?;
   
   packed_geometries.push(geometries[handle].pack(&mut gltf_source));
   return Ok(packed_geometries.len() - 1);
 }
 
+/// Writes a geometry's vertex positions as f32 xyz triples, the same bytes
+/// pack() puts in the GLB, and returns a pointer to them. The buffer is reused,
+/// so each call overwrites the previous result
+#[ffi]
+fn geometry_get_raw_vtx_ptr(handle: usize) -> FFIResult<FatPointer> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  let mut output = lock(&RAW_VTX_OUTPUT)?;
+  output.clear();
+  for value in geometries[handle].vertices_raw() {
+    output.extend_from_slice(unsafe { any_as_u8_slice(&value) });
+  }
+  
+  return FatPointer::try_from(output.as_ref());
+}
+
+/// Writes a geometry's triangle indices as u16 (under 65536 vertices) or u32,
+/// the same bytes pack() puts in the GLB, and returns a pointer to them. The
+/// buffer is reused, so each call overwrites the previous result
+#[ffi]
+fn geometry_get_raw_tri_ptr(handle: usize) -> FFIResult<FatPointer> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  let mut output = lock(&RAW_TRI_OUTPUT)?;
+  output.clear();
+  for value in geometries[handle].triangles_raw() {
+    output.extend_from_slice(unsafe { any_as_u8_slice(&value) });
+  }
+  
+  return FatPointer::try_from(output.as_ref());
+}
+
+/// Writes one vertex's position as three little-endian f64s and returns a
+/// pointer to them. The buffer is shared with other small queries, so each
+/// call overwrites the previous result
+#[ffi]
+fn geometry_get_vtx(handle: usize, vtx: u32) -> FFIResult<FatPointer> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  let vertex = geometries[handle].vertices.get(vtx as usize).ok_or(
+    ErrorCode::VtxOutOfBounds)?;
+  
+  let mut output = lock(&QUERY_OUTPUT)?;
+  output.clear();
+  for value in vertex.iter() {
+    output.extend_from_slice(&value.to_le_bytes());
+  }
+  
+  return FatPointer::try_from(output.as_ref());
+}
+
+/// Writes one triangle's vertex indices as three little-endian u32s and
+/// returns a pointer to them. Uses the same buffer as geometry_get_vtx()
+#[ffi]
+fn geometry_get_tri(handle: usize, tri: u32) -> FFIResult<FatPointer> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  let triangle = geometries[handle].triangles.get(tri as usize).ok_or(
+    ErrorCode::TriOutOfBounds)?;
+  
+  let mut output = lock(&QUERY_OUTPUT)?;
+  output.clear();
+  for index in triangle {
+    output.extend_from_slice(&index.to_le_bytes());
+  }
+  
+  return FatPointer::try_from(output.as_ref());
+}
+
+/// Writes a geometry's bounding box as six little-endian f64s (min xyz, then
+/// max xyz) and returns a pointer to them. Uses the same buffer as
+/// geometry_get_vtx()
+#[ffi]
+fn geometry_get_aabb(handle: usize) -> FFIResult<FatPointer> {
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  
+  let (min, max) = geometries[handle].aabb().ok_or(
+    ErrorCode::EmptyGeometry)?;
+  
+  let mut output = lock(&QUERY_OUTPUT)?;
+  output.clear();
+  for value in min.iter().chain(max.iter()) {
+    output.extend_from_slice(&value.to_le_bytes());
+  }
+  
+  return FatPointer::try_from(output.as_ref());
+}
+
+/// Packs a geometry once and registers `count` packed handles that all refer
+/// to the same accessors. Returns the first handle, so the handles are
+/// `handle..handle + count`. Each can be given to add_primitive_to_mesh()
+/// without duplicating buffer data
+#[ffi]
+fn geometry_pack_shared(handle: usize, count: usize) -> FFIResult<usize> {
+  if count == 0 { return Err(ErrorCode::ParameterOutOfRange) };
+  
+  // This lock must be saved in a variable before it can be used.
+  // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
+  // wrapped in a function
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
+    ErrorCode::NotInitialized)?;
+  
+  let geometries = lock(&GEOMETRIES)?;
+  if !geometries.contains(handle) { return Err(ErrorCode::HandleOutOfBounds) };
+  let mut packed_geometries = lock(&PACKED_GEOMETRIES)?;
+  
+  let packed = geometries[handle].pack(gltf_source);
+  let base = packed_geometries.len();
+  packed_geometries.extend(std::iter::repeat_n(packed, count));
+  return Ok(base);
+}
+
+/// Index of the accessor holding a packed geometry's vertex positions
+#[ffi]
+fn packed_get_vtx_accessor(packed: usize) -> FFIResult<usize> {
+  let packed_geometries = lock(&PACKED_GEOMETRIES)?;
+  if packed >= packed_geometries.len() {
+    return Err(ErrorCode::HandleOutOfBounds);
+  }
+  
+  return Ok(packed_geometries[packed].vertex_buffer as usize);
+}
+
+/// Index of the accessor holding a packed geometry's triangle indices, or
+/// 0xffffffff if it has no triangles
+#[ffi]
+fn packed_get_tri_accessor(packed: usize) -> FFIResult<usize> {
+  let packed_geometries = lock(&PACKED_GEOMETRIES)?;
+  if packed >= packed_geometries.len() {
+    return Err(ErrorCode::HandleOutOfBounds);
+  }
+  
+  return Ok(packed_geometries[packed].triangle_buffer.map_or(0xffffffff,
+    |i| i as usize));
+}
+
 struct DryRunWriter {
   bytes_written: usize,
 }
@@ -1350,12 +7340,20 @@ fn serialize() -> FFIResult<FatPointer> {
   // This lock must be saved in a variable before it can be used.
   // (lock(&GLTF_SOURCE)?).as_ref()... does not compile. This snippet cannot be
   // wrapped in a function
-  let gltf_source_option = lock(&GLTF_SOURCE)?;
-  let gltf_source = gltf_source_option.as_ref().ok_or(
+  let mut gltf_source_option = lock(&GLTF_SOURCE)?;
+  let gltf_source = gltf_source_option.as_mut().ok_or(
     ErrorCode::NotInitialized)?;
   
   let mut gltf_output = lock(&GLTF_OUTPUT)?;
   
+  let merged;
+  let (gltf_source, glb_bin) = if gltf_source.extra_bins.is_empty() {
+    (&*gltf_source, &gltf_source.glb_bin)
+  } else {
+    merged = gltf_source.merge_buffers();
+    (&merged.0, &merged.1)
+  };
+  
   let mut dry_run_writer = DryRunWriter::new();
   serde_json::ser::to_writer(&mut dry_run_writer, &gltf_source).unwrap();
   
@@ -1363,12 +7361,12 @@ fn serialize() -> FFIResult<FatPointer> {
   // padding
   let json_padding = (4 - dry_run_writer.bytes_written % 4) % 4;
   let json_length = dry_run_writer.bytes_written + json_padding;
-  let bin_padding = (4 - gltf_source.glb_bin.len() % 4) % 4;
-  let bin_length = gltf_source.glb_bin.len() + bin_padding;
+  let bin_padding = (4 - glb_bin.len() % 4) % 4;
+  let bin_length = glb_bin.len() + bin_padding;
   
   // Per GLB spec, overall length field INCLUDES headers
   let mut glb_length = 12 + 8 + json_length;
-  if gltf_source.glb_bin.len() > 0 {
+  if glb_bin.len() > 0 {
     glb_length += 8 + bin_length;
   }
   
@@ -1390,10 +7388,10 @@ fn serialize() -> FFIResult<FatPointer> {
   }
   
   // BIN chunk
-  if gltf_source.glb_bin.len() > 0 {
+  if glb_bin.len() > 0 {
     gltf_output.extend_from_slice(&(bin_length).to_le_bytes());
     gltf_output.append(&mut String::from("BIN\0").into_bytes());
-    gltf_output.extend(&gltf_source.glb_bin);
+    gltf_output.extend(glb_bin);
     for _ in 0..bin_padding {
       // Per GLB spec, BIN chunk is padded with zeroes
       gltf_output.push(0);
@@ -1404,3 +7402,54 @@ fn serialize() -> FFIResult<FatPointer> {
   
   return FatPointer::try_from(gltf_output.as_ref());
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  
+  #[test]
+  fn face_normal_matches_cube_sides() {
+    let cube = Geometry::cube();
+    
+    // Two triangles per side, in the order Geometry::cube() lists them
+    let expected = [
+      V3::z(), V3::z(),
+      V3::x(), V3::x(),
+      -V3::x(), -V3::x(),
+      V3::y(), V3::y(),
+      -V3::y(), -V3::y(),
+      -V3::z(), -V3::z(),
+    ];
+    
+    assert_eq!(cube.triangles.len(), expected.len());
+    for (i, normal) in expected.iter().enumerate() {
+      assert_eq!(cube.face_normal(i), *normal, "triangle {i}");
+    }
+  }
+  
+  #[test]
+  fn partial_subdivide_stays_closed() {
+    let mut cube = Geometry::cube();
+    cube.select_triangles(V3::new(-2.0, -2.0, 0.5), V3::new(2.0, 2.0, 2.0));
+    cube.subdivide(2).unwrap();
+    
+    assert_eq!(cube.selected_triangles().len(), 32);
+    assert!(cube.is_closed());
+    assert!(cube.is_winding_consistent());
+    assert!((cube.volume() - 8.0).abs() < 1e-9);
+  }
+  
+  #[test]
+  fn spatial_grid_handles_tiny_cells() {
+    let mut source = Geometry::cube();
+    source.select_invert();
+    
+    let mut cube = Geometry::cube();
+    cube.transfer_selection(&source, 1e-300).unwrap();
+    assert_eq!(cube.selected_vertices().len(), 8);
+    
+    let mut cube = Geometry::cube();
+    assert_eq!(cube.remove_doubles(f64::MIN_POSITIVE).unwrap(), 0);
+    assert_eq!(cube.vertices.len(), 8);
+  }
+}
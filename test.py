@@ -1,4 +1,5 @@
 import os, subprocess
+import pytest
 from pathlib import Path
 
 ####################
@@ -10,6 +11,17 @@ def setup_module():
     os.chdir(Path(__file__).parent / 'test-temp')
     os.symlink('../paraforge', 'paraforge')
 
+###########
+# Helpers #
+###########
+
+def read_glb(glb: bytes) -> tuple[dict, bytes]:
+    import json, struct
+    
+    json_length = struct.unpack('<L', glb[12:16])[0]
+    json_data = json.loads(glb[20:20 + json_length])
+    return json_data, glb[28 + json_length:]
+
 #########
 # Tests #
 #########
@@ -24,3 +36,429 @@ def test_demo():
     
     with open('../test-files/first_model.glb', 'rb') as f:
         assert result.stdout == f.read()
+
+def test_orient_like():
+    from paraforge import Geometry
+    
+    # Mirroring turns the cube inside out
+    mirrored = Geometry.Cube().s(-1, 1, 1)
+    assert mirrored.closed_volume() < 0
+    
+    mirrored.orient_like(Geometry.Cube())
+    assert mirrored.closed_volume() == 8
+
+def test_delete_stray_vertices():
+    from paraforge import Geometry
+    
+    # Deleting the middle cube's triangles leaves its vertices unreferenced,
+    # between vertices that are still used
+    geometry = Geometry.Cube().join(Geometry.Cube().t(4, 0, 0)) \
+        .join(Geometry.Cube().t(8, 0, 0))
+    geometry.select_triangles(3, -1, -1, 5, 1, 1).delete_triangles()
+    assert len(geometry.raw_vertices()) == 24*12
+    
+    geometry.delete_stray_vertices()
+    assert len(geometry.raw_vertices()) == 16*12
+    assert len(geometry.raw_triangles()) == 24*3*2
+    assert geometry.closed_volume() == 16
+
+def test_array_radial():
+    import math, struct
+    from paraforge import Geometry
+    
+    geometry = Geometry.ImportOBJ('v 2 0 0\nv 3 0 0\nv 2 1 0\nf 1 2 3\n')
+    geometry.select_triangles(-10, -10, -10, 10, 10, 10)
+    geometry.array_radial(6, (0, 0, 1))
+    
+    vertices = struct.unpack(f'<{len(geometry.raw_vertices())//4}f',
+        geometry.raw_vertices())
+    triangles = struct.unpack(f'<{len(geometry.raw_triangles())//2}H',
+        geometry.raw_triangles())
+    assert len(triangles) == 6*3
+    
+    angles = []
+    for i in range(0, len(triangles), 3):
+        x = sum(vertices[3*j] for j in triangles[i:i + 3])/3
+        y = sum(vertices[3*j + 1] for j in triangles[i:i + 3])/3
+        angles.append(math.degrees(math.atan2(y, x)) % 360)
+    
+    for i, angle in enumerate(angles):
+        error = (angle - angles[0] - 60*i) % 360
+        assert min(error, 360 - error) < 1e-3
+
+def test_select_invert():
+    from paraforge import Geometry
+    
+    cube = Geometry.Cube().select_vertex_list([0, 3, 5])
+    picked = cube.selection_centroid()
+    
+    cube.select_invert()
+    assert cube.selected_vertex_count() == 5
+    
+    # The cube is centered on the origin, so the centroids of the two parts
+    # balance out if the inverted selection is exactly the other 5 vertices
+    rest = cube.selection_centroid()
+    for axis in range(3):
+        assert abs(3*picked[axis] + 5*rest[axis]) < 1e-6
+
+def test_clone():
+    from paraforge import Geometry
+    
+    original = Geometry.Cube()
+    # Copied, since the raw buffer is reused by later calls
+    before = bytes(original.raw_vertices())
+    
+    copy = original.clone().t(5, 0, 0)
+    assert copy.handle != original.handle
+    assert bytes(copy.raw_vertices()) != before
+    assert bytes(original.raw_vertices()) == before
+
+def test_add_text_long():
+    from paraforge import Geometry
+    
+    # Both are longer than the 64 bytes a string transport holds, and cutting
+    # the second one at 64 bytes would split a 2-byte character
+    line = 'PARAFORGE '
+    single = Geometry.New().add_text(line)
+    repeated = Geometry.New().add_text(line*8)
+    assert len(repeated.raw_triangles()) == 8*len(single.raw_triangles())
+    
+    # Each character advances 6/7, so nothing may be cut off the end
+    assert repeated.aabb()[1][0] > 6/7*(len(line)*8 - 2)
+    
+    # Characters missing from the font are skipped
+    accented = Geometry.New().add_text('A' + 'É'*40)
+    assert accented.raw_triangles() == Geometry.New().add_text('A') \
+        .raw_triangles()
+
+def test_crease_survives_join():
+    from paraforge import Geometry
+    
+    cube = Geometry.Cube()
+    a, b, _ = cube.get_triangle(0)
+    cube.select_vertex_list([a, b]).set_crease(1.5)
+    assert cube.get_crease(b, a) == 1.5
+    
+    # Joined after another cube, so the creased edge's indices move up by 8
+    geometry = Geometry.Cube().t(4, 0, 0).join(cube)
+    assert geometry.get_crease(a + 8, b + 8) == 1.5
+    assert geometry.get_crease(a, b) == 0
+    
+    # Removing the first cube's vertices moves them back down
+    geometry.select_triangles(3, -1, -1, 5, 1, 1).delete_triangles()
+    geometry.delete_stray_vertices()
+    assert geometry.get_crease(a, b) == 1.5
+
+def test_union_convex():
+    import math
+    from paraforge import Geometry
+    
+    def union_volume(other: Geometry) -> float:
+        result = Geometry.Cube().union_convex(other)
+        assert result.nonmanifold_edge_count() == 0
+        assert result.hole_count() == 0
+        assert result.is_winding_consistent()
+        return result.closed_volume()
+    
+    # Overlapping, touching, and disjoint cubes, then one inside the other
+    assert union_volume(Geometry.Cube().t(1, 0, 0)) == 12
+    assert union_volume(Geometry.Cube().t(2, 0, 0)) == 16
+    assert union_volume(Geometry.Cube().t(5, 0, 0)) == 16
+    assert union_volume(Geometry.Cube().s(0.5, 0.5, 0.5)) == 8
+    
+    # Turned 45° about Z, the second cube's cross section is a diamond that
+    # overlaps the first's square by 2√2 - 1
+    diamond = Geometry.Cube().select_triangles(-1, -1, -1, 1, 1, 1) \
+        .rotate_euler_about((0, 0, 0), 0, 0, math.pi/4).t(1, 0, 0)
+    assert abs(union_volume(diamond) - (18 - 4*math.sqrt(2))) < 1e-5
+
+def test_checkpoint():
+    import paraforge
+    from paraforge import ErrorCode, Geometry, ParaforgeError
+    
+    cube = Geometry.Cube()
+    before = bytes(cube.raw_vertices())
+    
+    checkpoint = paraforge.checkpoint()
+    cube.t(5, 0, 0)
+    added = Geometry.Cube()
+    paraforge.restore(checkpoint)
+    assert bytes(cube.raw_vertices()) == before
+    
+    # Geometries made after the checkpoint are gone
+    with pytest.raises(ParaforgeError) as e:
+        added.raw_vertices()
+    assert e.value.code == ErrorCode.HandleOutOfBounds
+    
+    # Restoring keeps the checkpoint, so it can be restored again
+    cube.s(2, 2, 2)
+    paraforge.restore(checkpoint)
+    assert bytes(cube.raw_vertices()) == before
+
+def test_checkpoint_invalid():
+    import paraforge
+    from paraforge import ErrorCode, ParaforgeError
+    
+    kept = paraforge.checkpoint()
+    freed = paraforge.checkpoint()
+    paraforge.checkpoint_free(freed)
+    
+    for call in [paraforge.restore, paraforge.checkpoint_free]:
+        for id in [freed, freed + 1000]:
+            with pytest.raises(ParaforgeError) as e:
+                call(id)
+            assert e.value.code == ErrorCode.HandleOutOfBounds
+    
+    # Other ids stay valid
+    paraforge.restore(kept)
+    paraforge.checkpoint_free(kept)
+
+def test_pack_shared():
+    import paraforge
+    from paraforge import Geometry, Material, Node
+    
+    paraforge.init()
+    material = Material('Shared')
+    shared = Geometry.Cube().pack_shared(3)
+    assert [p.handle for p in shared] == list(range(shared[0].handle,
+        shared[0].handle + 3))
+    
+    mesh = Node('Shared').add_mesh('Shared')
+    for packed_geometry in shared:
+        mesh.add_primitive(packed_geometry, material)
+    json_data, bin_data = read_glb(paraforge.serialize())
+    
+    # The cube's 8 vertices and 12 triangles are only stored once
+    assert len(json_data['accessors']) == 2
+    assert len(bin_data) == 8*3*4 + 12*3*2
+    
+    primitives = json_data['meshes'][0]['primitives']
+    assert len(primitives) == 3
+    for primitive in primitives:
+        assert primitive['attributes']['POSITION'] == shared[0].vtx_accessor
+        assert primitive['indices'] == shared[0].tri_accessor
+
+def test_raw_buffers():
+    import paraforge
+    from paraforge import Geometry, Material, Node
+    
+    paraforge.init()
+    geometry = Geometry.Cube().t(1, 2, 3)
+    vertices = geometry.raw_vertices()
+    triangles = geometry.raw_triangles()
+    assert len(vertices) == 8*3*4
+    assert len(triangles) == 12*3*2
+    
+    # Same bytes as packing puts in the GLB
+    Node('Raw').add_mesh('Raw').add_primitive(geometry.pack(), Material('Raw'))
+    assert read_glb(paraforge.serialize())[1] == vertices + triangles
+    
+    # Later calls overwrite the buffer, but results already read are copies
+    assert Geometry.Cube().raw_vertices() != vertices
+    assert geometry.raw_vertices() == vertices
+
+def test_import_obj():
+    import paraforge
+    from paraforge import ErrorCode, Geometry, ParaforgeError
+    
+    # Quads, texture and normal indices, negative indices, and lines the
+    # importer ignores
+    cube = Geometry.ImportOBJ('''# Cube
+mtllib cube.mtl
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+vt 0 0
+vn 0 0 1
+usemtl Stone
+f 1 4 3 2
+f -4/1/1 -3/1/1 -2/1/1 -1/1/1
+f 1//1 2//1 6//1 5//1
+f 3/1 4/1 8/1 7/1
+f 1 5 8 4
+f 2 3 7 6
+''')
+    assert len(cube.raw_vertices()) == 8*3*4
+    assert len(cube.raw_triangles()) == 12*3*2
+    assert cube.get_triangle(2) == (4, 5, 6)
+    assert cube.closed_volume() == 8
+    
+    for text in ['v 0 0\n', 'v 0 0 0\nv 1 0 0\nf 1 2\n',
+    'v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n', 'f 1 x 3\n']:
+        with pytest.raises(ParaforgeError) as e:
+            Geometry.ImportOBJ(text)
+        assert e.value.code == ErrorCode.ParseError
+    
+    # Geometry.ImportOBJ() always sends valid UTF-8
+    data = b'v 0 0 \xff\n'
+    paraforge.write_data(data)
+    with pytest.raises(ParaforgeError) as e:
+        paraforge.wasm_call('import_obj', len(data))
+    assert e.value.code == ErrorCode.UnicodeError
+
+def test_buffer_per_mesh():
+    import paraforge
+    from paraforge import Geometry, Material, Node
+    
+    paraforge.init()
+    paraforge.set_buffer_per_mesh(True)
+    material = Material('Split')
+    mesh = Node('Split').add_mesh('Split')
+    
+    # The triangle's indices take 6 bytes, so the cube's buffer has to be
+    # padded to start on a 4-byte boundary
+    triangle = Geometry.ImportOBJ('v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n')
+    mesh.add_primitive(triangle.pack(), material)
+    mesh.add_primitive(Geometry.Cube().pack(), material)
+    json_data, bin_data = read_glb(paraforge.serialize())
+    
+    # GLB only has one BIN chunk, so the buffers are merged into buffer 0
+    assert json_data['buffers'] == [{'byteLength': 212}]
+    views = json_data['bufferViews']
+    assert [view['buffer'] for view in views] == [0, 0, 0, 0]
+    assert [view['byteOffset'] for view in views] == [0, 36, 44, 140]
+    assert bin_data[44:140] == Geometry.Cube().raw_vertices()
+
+def test_get_vertex():
+    from paraforge import ErrorCode, Geometry, ParaforgeError
+    
+    # 0.1 has no exact f32, so this checks the f64 is passed through intact
+    geometry = Geometry.ImportOBJ('v 0.1 -2.5 1e10\n')
+    assert geometry.get_vertex(0) == (0.1, -2.5, 1e10)
+    
+    with pytest.raises(ParaforgeError) as e:
+        geometry.get_vertex(1)
+    assert e.value.code == ErrorCode.VtxOutOfBounds
+
+def test_get_triangle():
+    from paraforge import ErrorCode, Geometry, ParaforgeError
+    
+    geometry = Geometry.ImportOBJ('v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n'
+        'f 1 2 3 4\n')
+    assert geometry.get_triangle(0) == (0, 1, 2)
+    assert geometry.get_triangle(1) == (0, 2, 3)
+    
+    with pytest.raises(ParaforgeError) as e:
+        geometry.get_triangle(2)
+    assert e.value.code == ErrorCode.TriOutOfBounds
+
+def test_aabb():
+    from paraforge import ErrorCode, Geometry, ParaforgeError
+    
+    geometry = Geometry.Cube().s(1, 2, 3).t(0.1, 0, -5)
+    assert geometry.aabb() == ((-0.9, -2, -8), (1.1, 2, -2))
+    
+    with pytest.raises(ParaforgeError) as e:
+        Geometry.New().aabb()
+    assert e.value.code == ErrorCode.EmptyGeometry
+
+def test_add_cone():
+    from paraforge import Geometry
+    
+    # With 4 segments the base is a square of area 2, and the cone is 2 tall
+    cone = Geometry.New().add_cone(4, 1, 0)
+    assert len(cone.raw_vertices()) == 5*3*4
+    assert cone.selected_vertex_count() == 5
+    assert cone.nonmanifold_edge_count() == 0
+    assert cone.hole_count() == 0
+    assert abs(cone.closed_volume() - 4/3) < 1e-6
+    assert cone.aabb() == ((-1, -1, -1), (1, 1, 1))
+    
+    # A nonzero top radius keeps a second ring instead of an apex
+    frustum = Geometry.New().add_cone(4, 1, 0.5, unit=True)
+    assert len(frustum.raw_vertices()) == 8*3*4
+    assert frustum.selected_vertex_count() == 8
+    assert frustum.nonmanifold_edge_count() == 0
+    assert frustum.hole_count() == 0
+    assert frustum.aabb() == ((-0.5, -0.5, -0.5), (0.5, 0.5, 0.5))
+
+def test_add_sphere():
+    import math
+    from paraforge import Geometry
+    
+    # Each ring repeats its first vertex, so the sphere is open along the seam
+    sphere = Geometry.New().add_sphere(8, 16)
+    assert len(sphere.raw_vertices()) == (7*17 + 2)*3*4
+    assert sphere.selected_vertex_count() == 7*17 + 2
+    assert sphere.hole_count() == 1
+    assert sphere.is_winding_consistent()
+    
+    # Welding the seam closes it
+    assert sphere.weld(1e-9) == 7
+    assert len(sphere.raw_vertices()) == (7*16 + 2)*3*4
+    assert sphere.nonmanifold_edge_count() == 0
+    assert sphere.hole_count() == 0
+    
+    # Inscribed in the unit sphere, so a little smaller
+    volume = sphere.closed_volume()
+    assert 0.9*4/3*math.pi < volume < 4/3*math.pi
+    
+    # Closed and convex, so union_convex() accepts it
+    sphere.union_convex(Geometry.Cube().s(0.5, 0.5, 0.5).t(1, 0, 0))
+    assert sphere.closed_volume() > volume
+
+def test_spin():
+    import math
+    from paraforge import Geometry
+    
+    # A unit square in the XZ plane, from x = 1 to 2, wound both ways. The
+    # caps' orientation can't be told from the net motion, which is parallel
+    # to the square after a half turn, against its first step after three
+    # quarters, and zero after a full turn
+    for face in ['1 2 3 4', '4 3 2 1']:
+        for angle, steps in [(math.pi/2, 8), (math.pi, 16), (1.5*math.pi, 24),
+        (2*math.pi, 32), (-2*math.pi, 32)]:
+            geometry = Geometry.ImportOBJ('v 1 0 0\nv 2 0 0\nv 2 0 1\n'
+                f'v 1 0 1\nf {face}\n')
+            geometry.select_triangles(-10, -10, -10, 10, 10, 10)
+            geometry.spin(steps, angle, (0, 0, 1))
+            
+            assert geometry.nonmanifold_edge_count() == 0
+            assert geometry.hole_count() == 0
+            assert geometry.is_winding_consistent()
+            
+            # Each step sweeps a wedge between radii 1 and 2
+            expected = steps*math.sin(abs(angle)/steps)*(2**2 - 1**2)/2
+            assert abs(geometry.closed_volume() - expected) < 1e-5
+        
+        # A full turn joins back onto the original vertices, with no caps left
+        # inside
+        assert len(geometry.raw_vertices()) == 4*32*3*4
+        assert geometry.selected_vertex_count() == 4
+
+def test_surface_area():
+    from paraforge import Geometry
+    
+    assert Geometry.New().surface_area() == 0
+    assert Geometry.New().add_grid(3, 2).surface_area() == 4
+    assert Geometry.New().add_grid(3, 2, unit=True).surface_area() == 1
+
+def test_add_torus_knot():
+    from paraforge import Geometry, ParaforgeError, ErrorCode
+    
+    knot = Geometry.New().add_torus_knot(2, 3, 64, 0.1)
+    assert knot.nonmanifold_edge_count() == 0
+    assert knot.hole_count() == 0
+    
+    # Non-coprime p and q would retrace the same path
+    for p, q in [(2, 4), (3, 6), (0, 1)]:
+        with pytest.raises(ParaforgeError) as e:
+            Geometry.New().add_torus_knot(p, q, 64, 0.1)
+        assert e.value.code == ErrorCode.ParameterOutOfRange
+
+def test_merge_average():
+    from paraforge import Geometry
+    
+    # All four corners of the unit grid weld at its center, and both triangles
+    # collapse
+    for merge in ['merge_average', 'merge_center']:
+        geometry = Geometry.New().add_grid(1, 1)
+        getattr(geometry, merge)()
+        assert len(geometry.raw_vertices()) == 3*4
+        assert geometry.get_vertex(0) == (0, 0, 0)
+        assert geometry.count_tris_above_area(-1) == 0